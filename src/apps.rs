@@ -0,0 +1,255 @@
+use std::{collections::BTreeSet, io::Cursor, path::{Path, PathBuf}, process::Command, str::FromStr};
+
+use serde::Deserialize;
+
+use crate::{boxart, output::info};
+
+/// App that is available for streaming on a host.
+#[derive(Debug, Clone)]
+pub struct MoonlightApp {
+	/// Title of the app, as used by `moonlight stream`.
+	pub title: String,
+
+	/// ID of the app on the host.
+	pub id: Option<u32>,
+
+	/// Path to the boxart of the app in the cache of this tool.
+	pub boxart: Option<PathBuf>,
+
+	/// Whether the host can stream the app in HDR.
+	pub hdr_supported: bool,
+
+	/// Whether the app was added to the host by an app collector, like the Steam library importer of Sunshine.
+	pub app_collector: bool,
+
+	/// Whether the app is hidden in Moonlight.
+	pub hidden: bool,
+
+	/// Whether Moonlight starts the app directly, instead of showing the app grid of the host.
+	pub direct_launch: bool,
+}
+
+/// Property of an app that filters and templates can refer to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AppProperty {
+	Hdr,
+	AppCollector,
+	Hidden,
+	DirectLaunch,
+}
+
+impl AppProperty {
+	pub const ALL: [AppProperty; 4] = [Self::Hdr, Self::AppCollector, Self::Hidden, Self::DirectLaunch];
+
+	/// Name of the property in `is:<name>` patterns, and as placeholder with underscores instead of dashes.
+	pub fn name(self) -> &'static str {
+		match self {
+			Self::Hdr => "hdr",
+			Self::AppCollector => "app-collector",
+			Self::Hidden => "hidden",
+			Self::DirectLaunch => "direct-launch",
+		}
+	}
+
+	/// Text that a placeholder for the property is replaced with when an app has it.
+	pub fn label(self) -> &'static str {
+		match self {
+			Self::Hdr => "HDR",
+			Self::AppCollector => "App Collector",
+			Self::Hidden => "Hidden",
+			Self::DirectLaunch => "Direct Launch",
+		}
+	}
+
+	pub fn of(self, app: &MoonlightApp) -> bool {
+		match self {
+			Self::Hdr => app.hdr_supported,
+			Self::AppCollector => app.app_collector,
+			Self::Hidden => app.hidden,
+			Self::DirectLaunch => app.direct_launch,
+		}
+	}
+}
+
+impl FromStr for AppProperty {
+	type Err = String;
+
+	fn from_str(name: &str) -> Result<Self, Self::Err> {
+		Self::ALL.into_iter().find(|p| p.name() == name).ok_or_else(|| {
+			let names: Vec<&str> = Self::ALL.iter().map(|p| p.name()).collect();
+			format!("unknown property '{name}', expected one of {}", names.join(", "))
+		})
+	}
+}
+
+/// Row of the output of `moonlight list --csv`, by the names in its header.
+///
+/// Only the name is required, so that older and newer versions of Moonlight with other columns can be used.
+#[derive(Debug, Deserialize)]
+struct CsvApp {
+	#[serde(rename = "Name")]
+	name: String,
+
+	#[serde(rename = "ID", default, deserialize_with = "csv::invalid_option")]
+	id: Option<u32>,
+
+	#[serde(rename = "HDR Support", default)]
+	hdr_supported: bool,
+
+	#[serde(rename = "App Collector Game", default)]
+	app_collector: bool,
+
+	#[serde(rename = "Hidden", default)]
+	hidden: bool,
+
+	#[serde(rename = "Direct Launch", default)]
+	direct_launch: bool,
+
+	#[serde(rename = "Boxart URL", default)]
+	boxart_url: Option<String>,
+}
+
+impl From<CsvApp> for MoonlightApp {
+	/// Converts a row to an app without boxart, which has to be copied into the cache first.
+	fn from(app: CsvApp) -> Self {
+		Self {
+			title: app.name,
+			id: app.id,
+			boxart: None,
+			hdr_supported: app.hdr_supported,
+			app_collector: app.app_collector,
+			hidden: app.hidden,
+			direct_launch: app.direct_launch,
+		}
+	}
+}
+
+/// Source of the apps that a host provides.
+pub trait AppSource {
+	/// Retrieves the apps that `host` provides.
+	fn apps(&self, host: &str) -> Result<Vec<MoonlightApp>, String>;
+}
+
+/// Method used to retrieve apps from a host.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+	/// Talk to the host directly, using the client certificate of Moonlight.
+	#[default]
+	Native,
+
+	/// Run `moonlight list --csv`.
+	Cli,
+}
+
+/// Retrieves apps by running `moonlight list --csv`.
+pub struct MoonlightCli {
+	/// Path to the Moonlight executable.
+	pub moonlight_path: PathBuf,
+}
+
+impl AppSource for MoonlightCli {
+	fn apps(&self, host: &str) -> Result<Vec<MoonlightApp>, String> {
+		let moonlight_apps = Command::new(&self.moonlight_path)
+			.args([
+				"list",
+				host,
+				"--csv"
+			])
+			.output()
+			.map_err(|e| format!("Failed to request apps from moonlight: {e}"))?;
+
+		if !moonlight_apps.status.success() {
+			let stderr = String::from_utf8_lossy(&moonlight_apps.stderr);
+			return Err(format!("Failed to get apps from Moonlight ({}): {}", moonlight_apps.status, stderr.trim()));
+		}
+
+		let mut apps = Vec::new();
+		for row in parse_csv(&moonlight_apps.stdout)? {
+			let source = row.boxart_url.as_deref().and_then(boxart::parse_url);
+			let mut app = MoonlightApp::from(row);
+
+			// Without an ID there is nothing to key the cached boxart by.
+			if let (Some(source), Some(id)) = (source, app.id) {
+				match boxart::cache(host, id, &source) {
+					Ok(path) => app.boxart = Some(path),
+					Err(e) => info!("Failed to retrieve boxart of '{}': {e}", app.title),
+				}
+			}
+			apps.push(app);
+		}
+
+		Ok(apps)
+	}
+}
+
+/// Parses the output of `moonlight list --csv`.
+fn parse_csv(csv: &[u8]) -> Result<Vec<CsvApp>, String> {
+	// Moonlight separates the names in the header with ", ", the values only with ",".
+	let mut reader = csv::ReaderBuilder::new().trim(csv::Trim::Headers).from_reader(Cursor::new(csv));
+
+	reader
+		.deserialize::<CsvApp>()
+		.map(|app| app.map_err(|e| format!("Failed to parse CSV from Moonlight: {e}")))
+		.collect()
+}
+
+/// Returns the flags that `moonlight stream` supports, like `--fps` and `--no-hdr`, as listed in its help.
+pub fn stream_flags(moonlight_path: &Path) -> Result<BTreeSet<String>, String> {
+	let output = Command::new(moonlight_path)
+		.args(["stream", "--help"])
+		.output()
+		.map_err(|e| format!("Failed to run Moonlight: {e}"))?;
+
+	// Help is printed on stdout by Qt, but some versions print it on stderr.
+	let help = [output.stdout, output.stderr].concat();
+	let help = String::from_utf8_lossy(&help);
+	let flags: BTreeSet<String> = help
+		.split(|c: char| c.is_whitespace() || c == ',' || c == '=' || c == '<')
+		.filter(|word| word.starts_with("--") && word.len() > 2 && word[2..].chars().all(|c| c.is_ascii_alphanumeric() || c == '-'))
+		.map(String::from)
+		.collect();
+
+	if flags.is_empty() {
+		return Err("Failed to find any options in the help of `moonlight stream`.".to_string());
+	}
+
+	Ok(flags)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parses_columns_by_header() {
+		let csv = "Name, ID, HDR Support, App Collector Game, Hidden, Direct Launch, Boxart URL\n\
+			Desktop,1,false,false,false,false,qrc:/res/no_app_image.png\n\
+			\"Cyberpunk \"\"2077\"\"\",2,true,true,true,true,file:///tmp/boxart/2.png\n";
+		let apps = parse_csv(csv.as_bytes()).unwrap();
+
+		assert_eq!(apps.len(), 2);
+		assert_eq!(apps[0].name, "Desktop");
+		assert_eq!(apps[0].id, Some(1));
+		assert_eq!(apps[0].boxart_url.as_deref(), Some("qrc:/res/no_app_image.png"));
+		assert!(!apps[0].hdr_supported && !apps[0].app_collector && !apps[0].hidden && !apps[0].direct_launch);
+		assert_eq!(apps[1].name, "Cyberpunk \"2077\"");
+		assert_eq!(apps[1].boxart_url.as_deref(), Some("file:///tmp/boxart/2.png"));
+		assert!(apps[1].hdr_supported && apps[1].app_collector && apps[1].hidden && apps[1].direct_launch);
+	}
+
+	#[test]
+	fn tolerates_extra_and_missing_columns() {
+		let csv = "ID, Name, Some Future Column\n7,Steam Big Picture,whatever\nnot a number,  Spaces  ,\n";
+		let apps = parse_csv(csv.as_bytes()).unwrap();
+
+		assert_eq!(apps[0].name, "Steam Big Picture");
+		assert_eq!(apps[0].id, Some(7));
+		assert!(!apps[0].hdr_supported);
+		assert_eq!(apps[0].boxart_url, None);
+		assert_eq!(apps[1].name, "  Spaces  ");
+		assert_eq!(apps[1].id, None);
+
+		assert!(parse_csv(b"ID, HDR Support\n1,true\n").is_err());
+	}
+}
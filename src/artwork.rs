@@ -0,0 +1,236 @@
+use std::path::{Path, PathBuf};
+
+use image::{imageops::{self, FilterType}, DynamicImage, ImageFormat, RgbaImage};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::placeholder;
+
+/// Extensions that Steam accepts for custom artwork, used to clean up artwork of removed shortcuts.
+const EXTENSIONS: [&str; 3] = ["png", "jpg", "jpeg"];
+
+/// Images whose aspect ratio is within this fraction of the target are cropped instead of padded.
+const CROP_TOLERANCE: f32 = 0.15;
+
+/// Artwork slots that Steam shows in its library.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArtworkKind {
+	/// Portrait grid image, shown in the library overview.
+	Portrait,
+
+	/// Landscape grid image, shown in "recent games" and Big Picture.
+	Landscape,
+
+	/// Wide banner shown at the top of the game page.
+	Hero,
+
+	/// Logo that is drawn on top of the hero image.
+	Logo,
+}
+
+impl ArtworkKind {
+	pub const ALL: [ArtworkKind; 4] = [ArtworkKind::Portrait, ArtworkKind::Landscape, ArtworkKind::Hero, ArtworkKind::Logo];
+
+	/// File name (without extension) that Steam expects for this kind of artwork.
+	fn file_stem(self, app_id: u32) -> String {
+		match self {
+			ArtworkKind::Portrait => format!("{app_id}p"),
+			ArtworkKind::Landscape => format!("{app_id}"),
+			ArtworkKind::Hero => format!("{app_id}_hero"),
+			ArtworkKind::Logo => format!("{app_id}_logo"),
+		}
+	}
+
+	/// Size in pixels that Steam expects for this kind of artwork.
+	pub fn size(self) -> (u32, u32) {
+		match self {
+			ArtworkKind::Portrait => (600, 900),
+			ArtworkKind::Landscape => (920, 430),
+			ArtworkKind::Hero => (1920, 620),
+			ArtworkKind::Logo => (640, 360),
+		}
+	}
+
+	/// Converts an image into artwork for this slot.
+	pub fn render(self, image: &DynamicImage) -> RgbaImage {
+		let (width, height) = self.size();
+		match self {
+			// The logo is drawn on top of the hero, so it keeps its aspect ratio and has a transparent background.
+			ArtworkKind::Logo => fit(image, width, height, None),
+			_ => {
+				let source_ratio = image.width() as f32 / image.height().max(1) as f32;
+				let target_ratio = width as f32 / height as f32;
+				if (source_ratio / target_ratio - 1.0).abs() <= CROP_TOLERANCE {
+					image.resize_to_fill(width, height, FilterType::Lanczos3).to_rgba8()
+				} else {
+					fit(image, width, height, Some(blurred_background(image, width, height)))
+				}
+			},
+		}
+	}
+}
+
+/// What the artwork of a shortcut was generated from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ArtworkSource {
+	/// Placeholder showing the title of the app, see [`write_placeholder_artwork`].
+	Placeholder { title: String, host: String },
+
+	/// Boxart of the app, with a hash of the image since cached boxart keeps its path when it changes.
+	Boxart { path: PathBuf, hash: String },
+}
+
+impl ArtworkSource {
+	/// Boxart at `path`, which is read to determine its hash.
+	pub fn boxart(path: &Path) -> Result<Self, String> {
+		let image = std::fs::read(path).map_err(|e| format!("Failed to read boxart '{}': {e}", path.display()))?;
+		Ok(ArtworkSource::Boxart { path: path.to_path_buf(), hash: format!("{:x}", Sha256::digest(image)) })
+	}
+}
+
+/// Artwork that this tool generated for a shortcut.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GeneratedArtwork {
+	pub source: ArtworkSource,
+
+	/// Hash of the artwork files as they were written, see [`files_hash`].
+	pub files: String,
+}
+
+/// Directory in which Steam looks for custom artwork of a user.
+pub fn grid_dir(userdata_dir: &Path) -> PathBuf {
+	userdata_dir.join("config/grid")
+}
+
+/// Writes the artwork of a shortcut to every slot, based on `source`.
+pub fn write(grid_dir: &Path, app_id: u32, source: ArtworkSource) -> Result<GeneratedArtwork, String> {
+	match &source {
+		ArtworkSource::Placeholder { title, host } => write_placeholder_artwork(grid_dir, app_id, title, host)?,
+		ArtworkSource::Boxart { path, .. } => write_artwork(grid_dir, app_id, path)?,
+	}
+
+	let files = files_hash(grid_dir, app_id).ok_or_else(|| format!("Failed to find the artwork written for {app_id}."))?;
+	Ok(GeneratedArtwork { source, files })
+}
+
+/// Writes the artwork of a shortcut to every slot, based on its boxart.
+pub fn write_artwork(grid_dir: &Path, app_id: u32, boxart: &Path) -> Result<(), String> {
+	let image = image::open(boxart).map_err(|e| format!("Failed to read boxart '{}': {e}", boxart.display()))?;
+	write_images(grid_dir, app_id, |kind| kind.render(&image))
+}
+
+/// Writes generated placeholder artwork to every slot, for shortcuts of apps without boxart.
+pub fn write_placeholder_artwork(grid_dir: &Path, app_id: u32, title: &str, host: &str) -> Result<(), String> {
+	write_images(grid_dir, app_id, |kind| placeholder::render(kind, title, host))
+}
+
+fn write_images(grid_dir: &Path, app_id: u32, render: impl Fn(ArtworkKind) -> RgbaImage) -> Result<(), String> {
+	std::fs::create_dir_all(grid_dir)
+		.map_err(|e| format!("Failed to create grid directory '{}': {e}", grid_dir.display()))?;
+
+	// Remove artwork with other extensions, otherwise Steam may keep showing those.
+	remove_artwork(grid_dir, app_id)?;
+
+	for kind in ArtworkKind::ALL {
+		let path = grid_dir.join(format!("{}.png", kind.file_stem(app_id)));
+		render(kind)
+			.save_with_format(&path, ImageFormat::Png)
+			.map_err(|e| format!("Failed to write artwork '{}': {e}", path.display()))?;
+	}
+
+	Ok(())
+}
+
+/// Hash of the names and contents of all artwork files of a shortcut, or `None` if it has none.
+///
+/// This tells whether artwork that this tool generated was replaced by the user since.
+pub fn files_hash(grid_dir: &Path, app_id: u32) -> Option<String> {
+	let mut hasher = Sha256::new();
+	let mut found = false;
+	for kind in ArtworkKind::ALL {
+		for extension in EXTENSIONS {
+			let name = format!("{}.{extension}", kind.file_stem(app_id));
+			if let Ok(contents) = std::fs::read(grid_dir.join(&name)) {
+				hasher.update(name.as_bytes());
+				hasher.update(contents);
+				found = true;
+			}
+		}
+	}
+
+	found.then(|| format!("{:x}", hasher.finalize()))
+}
+
+/// Removes all artwork of a shortcut.
+pub fn remove_artwork(grid_dir: &Path, app_id: u32) -> Result<(), String> {
+	for kind in ArtworkKind::ALL {
+		for extension in EXTENSIONS {
+			let path = grid_dir.join(format!("{}.{extension}", kind.file_stem(app_id)));
+			if path.exists() {
+				std::fs::remove_file(&path).map_err(|e| format!("Failed to remove artwork '{}': {e}", path.display()))?;
+			}
+		}
+	}
+
+	Ok(())
+}
+
+/// Renames the artwork of a shortcut whose app ID changed, replacing any artwork that exists for the new app ID.
+pub fn rename_artwork(grid_dir: &Path, old_app_id: u32, new_app_id: u32) -> Result<(), String> {
+	transfer_artwork(grid_dir, old_app_id, new_app_id, |old_path, new_path| {
+		std::fs::rename(old_path, new_path).map_err(|e| format!("Failed to rename artwork '{}': {e}", old_path.display()))
+	})
+}
+
+/// Copies the artwork of a shortcut whose app ID changed, for when another shortcut still has the old app ID.
+pub fn copy_artwork(grid_dir: &Path, old_app_id: u32, new_app_id: u32) -> Result<(), String> {
+	transfer_artwork(grid_dir, old_app_id, new_app_id, |old_path, new_path| {
+		std::fs::copy(old_path, new_path)
+			.map(|_| ())
+			.map_err(|e| format!("Failed to copy artwork '{}': {e}", old_path.display()))
+	})
+}
+
+fn transfer_artwork(
+	grid_dir: &Path,
+	old_app_id: u32,
+	new_app_id: u32,
+	transfer: impl Fn(&Path, &Path) -> Result<(), String>,
+) -> Result<(), String> {
+	for kind in ArtworkKind::ALL {
+		for extension in EXTENSIONS {
+			let old_path = grid_dir.join(format!("{}.{extension}", kind.file_stem(old_app_id)));
+			if old_path.exists() {
+				transfer(&old_path, &grid_dir.join(format!("{}.{extension}", kind.file_stem(new_app_id))))?;
+			}
+		}
+	}
+
+	Ok(())
+}
+
+/// Scales an image to fit inside the given size and centers it on the background (or a transparent canvas).
+fn fit(image: &DynamicImage, width: u32, height: u32, background: Option<RgbaImage>) -> RgbaImage {
+	let mut canvas = background.unwrap_or_else(|| RgbaImage::new(width, height));
+	let scaled = image.resize(width, height, FilterType::Lanczos3).to_rgba8();
+
+	let x = (width - scaled.width()) / 2;
+	let y = (height - scaled.height()) / 2;
+	imageops::overlay(&mut canvas, &scaled, x.into(), y.into());
+
+	canvas
+}
+
+/// Creates a darkened and blurred version of an image that covers the given size.
+fn blurred_background(image: &DynamicImage, width: u32, height: u32) -> RgbaImage {
+	// Blurring a downscaled image is much faster and looks the same once scaled back up.
+	const SCALE: u32 = 8;
+
+	let small = image.resize_to_fill((width / SCALE).max(1), (height / SCALE).max(1), FilterType::Triangle);
+	let blurred = small.fast_blur(4.0);
+	let mut background = blurred.resize_exact(width, height, FilterType::Triangle).to_rgba8();
+	imageops::colorops::brighten_in_place(&mut background, -60);
+
+	background
+}
@@ -0,0 +1,153 @@
+use std::{
+	fs::File,
+	io::{ErrorKind, Write},
+	path::{Path, PathBuf},
+};
+
+use steam_shortcuts_util::parse_shortcuts;
+
+use crate::{config::APP_NAME, output::info, shortcuts, time::UtcTime};
+
+/// Backup of the shortcuts file of a Steam user.
+#[derive(Debug)]
+pub struct Backup {
+	/// Time (UTC) at which the backup was made, formatted as `YYYYMMDD-HHMMSS`, followed by a counter (`-2`, `-3`, ...)
+	/// for further backups made within the same second.
+	pub timestamp: String,
+
+	/// Location of the backup.
+	pub path: PathBuf,
+}
+
+/// Directory containing the backups of the shortcuts file of a Steam user.
+///
+/// Backups are kept per user, named after the user directory (the account ID).
+fn backup_dir(userdata_dir: &Path) -> Result<PathBuf, String> {
+	let user = userdata_dir
+		.file_name()
+		.ok_or_else(|| format!("Failed to determine Steam user of '{}'.", userdata_dir.display()))?;
+
+	xdg::BaseDirectories::with_prefix(APP_NAME)
+		.map_err(|e| format!("Failed to determine state directory: {e}"))?
+		.create_state_directory(Path::new("backups").join(user))
+		.map_err(|e| format!("Failed to create backup directory: {e}"))
+}
+
+/// Lists the backups of a Steam user, oldest first.
+pub fn list(userdata_dir: &Path) -> Result<Vec<Backup>, String> {
+	let dir = backup_dir(userdata_dir)?;
+
+	let mut backups: Vec<Backup> = std::fs::read_dir(&dir)
+		.map_err(|e| format!("Failed to read backup directory '{}': {e}", dir.display()))?
+		.filter_map(Result::ok)
+		.filter_map(|entry| {
+			let name = entry.file_name().into_string().ok()?;
+			let timestamp = name.strip_prefix("shortcuts-")?.strip_suffix(".vdf")?.to_string();
+			Some(Backup { timestamp, path: entry.path() })
+		})
+		.collect();
+	backups.sort_by(|a, b| sort_key(&a.timestamp).cmp(&sort_key(&b.timestamp)));
+
+	Ok(backups)
+}
+
+/// Orders timestamps chronologically: times sort as strings, the counters within a second as numbers.
+fn sort_key(timestamp: &str) -> (&str, u32) {
+	match timestamp.split_at_checked(15) {
+		Some((time, counter)) if !counter.is_empty() => (time, counter.trim_start_matches('-').parse().unwrap_or(0)),
+		_ => (timestamp, 1),
+	}
+}
+
+/// Copies the shortcuts file to a new backup, then removes the oldest backups so at most `retention` remain.
+///
+/// Nothing is done when the shortcuts file doesn't exist yet or `retention` is zero.
+pub fn create(userdata_dir: &Path, shortcuts_path: &Path, retention: usize) -> Result<Option<Backup>, String> {
+	if retention == 0 || !shortcuts_path.exists() {
+		return Ok(None);
+	}
+
+	let backup = copy(userdata_dir, shortcuts_path)?;
+
+	let backups = list(userdata_dir)?;
+	for backup in &backups[..backups.len().saturating_sub(retention)] {
+		std::fs::remove_file(&backup.path)
+			.map_err(|e| format!("Failed to remove old backup '{}': {e}", backup.path.display()))?;
+	}
+
+	Ok(Some(backup))
+}
+
+/// Copies the shortcuts file to a new backup, without removing old backups.
+fn copy(userdata_dir: &Path, shortcuts_path: &Path) -> Result<Backup, String> {
+	let time = UtcTime::now();
+	let time = format!(
+		"{:04}{:02}{:02}-{:02}{:02}{:02}",
+		time.year, time.month, time.day, time.hour, time.minute, time.second,
+	);
+	let contents = std::fs::read(shortcuts_path).map_err(|e| format!("Failed to read shortcuts file: {e}"))?;
+
+	// Backups made within the same second, like by a restore right after a sync, get a counter instead of replacing
+	// each other.
+	let dir = backup_dir(userdata_dir)?;
+	let mut counter = 1;
+	let (timestamp, path, mut file) = loop {
+		let timestamp = if counter == 1 { time.clone() } else { format!("{time}-{counter}") };
+		let path = dir.join(format!("shortcuts-{timestamp}.vdf"));
+		match File::create_new(&path) {
+			Ok(file) => break (timestamp, path, file),
+			Err(e) if e.kind() == ErrorKind::AlreadyExists => counter += 1,
+			Err(e) => return Err(format!("Failed to create backup '{}': {e}", path.display())),
+		}
+	};
+	file.write_all(&contents)
+		.and_then(|_| file.sync_all())
+		.map_err(|e| format!("Failed to back up shortcuts to '{}': {e}", path.display()))?;
+
+	Ok(Backup { timestamp, path })
+}
+
+/// Replaces the shortcuts file with the backup made at `timestamp`.
+///
+/// The backup is checked to be a valid shortcuts file first, and the current shortcuts file is backed up
+/// before it is replaced, so a restore can be undone. Old backups are not removed here, since that could remove the
+/// backup being restored; the next backup that is made removes them.
+pub fn restore(userdata_dir: &Path, shortcuts_path: &Path, timestamp: &str) -> Result<(), String> {
+	let backup = list(userdata_dir)?
+		.into_iter()
+		.find(|b| b.timestamp == timestamp)
+		.ok_or_else(|| format!("Failed to find a backup made at '{timestamp}', see `{} backups list`.", env!("CARGO_PKG_NAME")))?;
+
+	let contents = std::fs::read(&backup.path)
+		.map_err(|e| format!("Failed to read backup '{}': {e}", backup.path.display()))?;
+	let shortcuts = parse_shortcuts(&contents)
+		.map_err(|e| format!("Backup '{}' is not a valid shortcuts file: {e}", backup.path.display()))?;
+
+	if std::fs::read(shortcuts_path).is_ok_and(|current| current == contents) {
+		info!("Shortcuts file already matches backup '{timestamp}'.");
+		return Ok(());
+	}
+
+	// Keep the current file around, in case the wrong backup was picked.
+	if shortcuts_path.exists() {
+		copy(userdata_dir, shortcuts_path)?;
+	}
+
+	shortcuts::write_atomic(shortcuts_path, &contents).map_err(|e| format!("Failed to restore shortcuts file: {e}"))?;
+	info!("Restored {} shortcut(s) from backup '{timestamp}'.", shortcuts.len());
+
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn orders_backups_made_within_a_second() {
+		let mut timestamps = ["20260101-120000-10", "20260101-120000-2", "20260101-120001", "20260101-120000"];
+		timestamps.sort_by_key(|t| sort_key(t));
+
+		assert_eq!(timestamps, ["20260101-120000", "20260101-120000-2", "20260101-120000-10", "20260101-120001"]);
+	}
+}
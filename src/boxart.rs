@@ -0,0 +1,202 @@
+use std::path::{Path, PathBuf};
+
+use crate::{config::APP_NAME, gamestream};
+
+/// Location of the boxart of an app, as reported by Moonlight.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BoxartSource {
+	/// Image on the local file system, usually in the cache of Moonlight.
+	File(PathBuf),
+
+	/// Image that has to be downloaded.
+	Http {
+		secure: bool,
+		host: String,
+		port: Option<u16>,
+
+		/// Path including the query, as it appears in the URL.
+		path: String,
+	},
+}
+
+/// Parses the boxart URL that Moonlight reports for an app.
+///
+/// Returns `None` for URLs that don't point to an image of the app, like the placeholder that Moonlight bundles
+/// (`qrc:/res/no_app_image.png`).
+pub fn parse_url(url: &str) -> Option<BoxartSource> {
+	let url = url.trim();
+
+	if let Some(rest) = url.strip_prefix("file://") {
+		// Only local files are supported: `file:///path` or `file://localhost/path`.
+		let path = rest.strip_prefix("localhost").unwrap_or(rest);
+		if !path.starts_with('/') {
+			return None;
+		}
+		return Some(BoxartSource::File(path_from_bytes(percent_decode(path))));
+	}
+	if url.starts_with('/') {
+		return Some(BoxartSource::File(PathBuf::from(url)));
+	}
+
+	let (secure, rest) = if let Some(rest) = url.strip_prefix("https://") {
+		(true, rest)
+	} else {
+		(false, url.strip_prefix("http://")?)
+	};
+	let (authority, path) = match rest.find(['/', '?']) {
+		Some(index) => (&rest[..index], rest[index..].to_string()),
+		None => (rest, "/".to_string()),
+	};
+	let path = if path.starts_with('?') { format!("/{path}") } else { path };
+	// Credentials in URLs aren't supported, and the fragment is never sent.
+	let authority = authority.rsplit_once('@').map_or(authority, |(_, host)| host);
+	let path = path.split_once('#').map_or(path.as_str(), |(path, _)| path).to_string();
+
+	let (host, port) = gamestream::split_address(authority);
+	if host.is_empty() {
+		return None;
+	}
+
+	Some(BoxartSource::Http { secure, host, port, path })
+}
+
+/// Decodes `%XX` escapes, leaving invalid escapes as they are.
+fn percent_decode(text: &str) -> Vec<u8> {
+	let bytes = text.as_bytes();
+	let mut decoded = Vec::with_capacity(bytes.len());
+
+	let mut i = 0;
+	while i < bytes.len() {
+		let escaped = (bytes[i] == b'%')
+			.then(|| bytes.get(i + 1..i + 3))
+			.flatten()
+			.and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+		match escaped {
+			Some(byte) => {
+				decoded.push(byte);
+				i += 3;
+			},
+			None => {
+				decoded.push(bytes[i]);
+				i += 1;
+			},
+		}
+	}
+
+	decoded
+}
+
+#[cfg(unix)]
+fn path_from_bytes(bytes: Vec<u8>) -> PathBuf {
+	use std::{ffi::OsString, os::unix::ffi::OsStringExt};
+
+	PathBuf::from(OsString::from_vec(bytes))
+}
+
+#[cfg(not(unix))]
+fn path_from_bytes(bytes: Vec<u8>) -> PathBuf {
+	PathBuf::from(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Reads or downloads the boxart of an app into the cache, returning the path of the cached image.
+pub fn cache(host: &str, app_id: u32, source: &BoxartSource) -> Result<PathBuf, String> {
+	let image = match source {
+		BoxartSource::File(path) => {
+			std::fs::read(path).map_err(|e| format!("Failed to read boxart from '{}': {e}", path.display()))?
+		},
+		BoxartSource::Http { secure: false, host, port, path } => {
+			gamestream::http_get(host, port.unwrap_or(80), path, gamestream::TIMEOUT)?
+		},
+		BoxartSource::Http { secure: true, host, port, path } => gamestream::https_get(host, port.unwrap_or(443), path)?,
+	};
+
+	store(host, app_id, &image)
+}
+
+/// Stores the boxart of an app in the cache, returning the path of the cached image.
+///
+/// Images are kept in the XDG cache directory by host and app ID, so shortcuts keep pointing at the same path
+/// when the boxart changes, and Moonlight purging its own cache doesn't affect them.
+pub fn store(host: &str, app_id: u32, image: &[u8]) -> Result<PathBuf, String> {
+	let extension = match image::guess_format(image) {
+		Ok(image::ImageFormat::Jpeg) => "jpg",
+		Ok(image::ImageFormat::Png) => "png",
+		_ => return Err("Boxart is not a PNG or JPEG image.".to_string()),
+	};
+
+	let dir = xdg::BaseDirectories::with_prefix(APP_NAME)
+		.map_err(|e| format!("Failed to determine cache directory: {e}"))?
+		.create_cache_directory(format!("boxart/{}", host_dir_name(host)))
+		.map_err(|e| format!("Failed to create cache directory: {e}"))?;
+
+	let path = dir.join(format!("{app_id}.{extension}"));
+	// Rewriting an unchanged image would only make Steam reload it.
+	if std::fs::read(&path).is_ok_and(|existing| existing == image) {
+		return Ok(path);
+	}
+	std::fs::write(&path, image).map_err(|e| format!("Failed to write boxart to '{}': {e}", path.display()))?;
+
+	Ok(path)
+}
+
+/// Whether an image is boxart that was cached by this tool or by Moonlight, rather than an icon chosen by the user.
+///
+/// Moonlight keeps boxart in `Moonlight Game Streaming Project/Moonlight/boxart` within its (possibly Flatpak) cache
+/// directory, this tool in `moonlight-steam-shortcuts/boxart`.
+pub fn is_cached(path: &Path) -> bool {
+	let mut components = path.components().map(|c| c.as_os_str());
+	components.any(|c| c == APP_NAME || c == "Moonlight Game Streaming Project") && components.any(|c| c == "boxart")
+}
+
+/// Name of the cache directory of a host, which is its address with characters that don't belong in file names
+/// replaced.
+fn host_dir_name(host: &str) -> String {
+	host.chars().map(|c| if c.is_alphanumeric() || "-_.".contains(c) { c } else { '_' }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parses_file_urls() {
+		assert_eq!(
+			parse_url("file:///home/deck/.cache/Moonlight/boxart/My%20Game%E2%84%A2.png"),
+			Some(BoxartSource::File(PathBuf::from("/home/deck/.cache/Moonlight/boxart/My Game™.png")))
+		);
+		assert_eq!(parse_url("file://localhost/tmp/a.png"), Some(BoxartSource::File(PathBuf::from("/tmp/a.png"))));
+		assert_eq!(parse_url("file:///tmp/100%.png"), Some(BoxartSource::File(PathBuf::from("/tmp/100%.png"))));
+		assert_eq!(parse_url("file://server/share/a.png"), None);
+		assert_eq!(parse_url("qrc:/res/no_app_image.png"), None);
+		assert_eq!(parse_url(""), None);
+	}
+
+	#[test]
+	fn parses_http_urls() {
+		assert_eq!(
+			parse_url("https://example.com/boxart/1.png?size=large#top"),
+			Some(BoxartSource::Http {
+				secure: true,
+				host: "example.com".to_string(),
+				port: None,
+				path: "/boxart/1.png?size=large".to_string(),
+			})
+		);
+		assert_eq!(
+			parse_url("http://[fe80::1]:8080"),
+			Some(BoxartSource::Http { secure: false, host: "fe80::1".to_string(), port: Some(8080), path: "/".to_string() })
+		);
+		assert_eq!(parse_url("ftp://example.com/a.png"), None);
+	}
+
+	#[test]
+	fn recognizes_cached_boxart() {
+		assert!(is_cached(Path::new("/home/deck/.cache/moonlight-steam-shortcuts/boxart/10.0.0.7/42.png")));
+		assert!(is_cached(Path::new("/home/deck/.cache/Moonlight Game Streaming Project/Moonlight/boxart/1234/42.png")));
+		assert!(is_cached(Path::new(
+			"/home/deck/.var/app/com.moonlight_stream.Moonlight/cache/Moonlight Game Streaming Project/Moonlight/boxart/42.png"
+		)));
+		assert!(!is_cached(Path::new("/home/deck/Pictures/boxart/42.png")));
+		assert!(!is_cached(Path::new("/home/deck/.cache/moonlight-steam-shortcuts/placeholder.png")));
+	}
+}
@@ -0,0 +1,62 @@
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+/// Location of the collections of a Steam user, which Steam keeps in a local copy of its cloud storage.
+fn collections_path(userdata_dir: &Path) -> PathBuf {
+	userdata_dir.join("config/cloudstorage/cloud-storage-namespace-1.json")
+}
+
+/// Replaces app IDs in the collections of a Steam user, returning the number of collections that changed.
+///
+/// The file is a list of `[key, entry]` pairs, where the entries of collections (`user-collections.*`) contain the
+/// collection as a JSON string, with the app IDs in its `added` and `removed` lists.
+pub fn remap_app_ids(userdata_dir: &Path, remapped: &[(u32, u32)]) -> Result<usize, String> {
+	let path = collections_path(userdata_dir);
+	if remapped.is_empty() || !path.exists() {
+		return Ok(0);
+	}
+
+	let contents = std::fs::read_to_string(&path)
+		.map_err(|e| format!("Failed to read collections '{}': {e}", path.display()))?;
+	let mut namespace: Value = serde_json::from_str(&contents)
+		.map_err(|e| format!("Failed to parse collections '{}': {e}", path.display()))?;
+
+	let mut changed = 0;
+	for entry in namespace.as_array_mut().into_iter().flatten().filter_map(|pair| pair.get_mut(1)) {
+		let is_collection = entry.get("key").and_then(Value::as_str).is_some_and(|k| k.starts_with("user-collections."));
+		let Some(Value::String(value)) = entry.get_mut("value").filter(|_| is_collection) else {
+			continue;
+		};
+		// Deleted and dynamic collections don't have lists of apps.
+		let Ok(mut collection) = serde_json::from_str::<Value>(value) else {
+			continue;
+		};
+
+		let mut collection_changed = false;
+		for list in ["added", "removed"] {
+			for app_id in collection.get_mut(list).and_then(Value::as_array_mut).into_iter().flatten() {
+				let new_id = app_id.as_u64().and_then(|id| remapped.iter().find(|(old, _)| u64::from(*old) == id));
+				if let Some((_, new_id)) = new_id {
+					*app_id = Value::from(*new_id);
+					collection_changed = true;
+				}
+			}
+		}
+
+		if collection_changed {
+			*value = serde_json::to_string(&collection).map_err(|e| format!("Failed to serialize collection: {e}"))?;
+			changed += 1;
+		}
+	}
+
+	if changed > 0 {
+		let contents = serde_json::to_string(&namespace).map_err(|e| format!("Failed to serialize collections: {e}"))?;
+		let temp_path = path.with_extension(format!("{}.tmp", std::process::id()));
+		std::fs::write(&temp_path, contents)
+			.and_then(|_| std::fs::rename(&temp_path, &path))
+			.map_err(|e| format!("Failed to write collections '{}': {e}", path.display()))?;
+	}
+
+	Ok(changed)
+}
@@ -0,0 +1,1020 @@
+use std::{collections::BTreeMap, path::{Path, PathBuf}, str::FromStr};
+
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::{apps::{AppProperty, Backend, MoonlightApp}, shortcuts::AppIdMode, steam::RunningPolicy};
+use toml::{de::{DeTable, DeValue}, Spanned};
+
+/// Name of the directory that contains the files of this tool in the XDG directories.
+pub const APP_NAME: &str = "moonlight-steam-shortcuts";
+
+/// Configuration file, every value can be overridden on the command line.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+	/// Path to the Moonlight executable.
+	pub moonlight: Option<PathBuf>,
+
+	/// How to retrieve the apps of a host.
+	pub backend: Option<Backend>,
+
+	/// Which Steam user to add the shortcuts to.
+	pub steam: SteamConfig,
+
+	/// How existing shortcuts are synchronized.
+	pub sync: SyncConfig,
+
+	/// How backups of the shortcuts file are kept.
+	pub backups: BackupConfig,
+
+	/// Extra tags to add to every shortcut, which can contain the same placeholders as the title template.
+	pub tags: Vec<String>,
+
+	/// Template for the names of the shortcuts, with placeholders like `{title}`, `{host}` and `{host_name}`.
+	pub title_template: Option<String>,
+
+	/// Rules that rename the titles of apps, applied in order before the title template.
+	pub rename: Vec<RenameRule>,
+
+	/// Which apps to create shortcuts for.
+	pub filters: Filters,
+
+	/// Options passed to Moonlight when streaming.
+	pub stream: StreamOptions,
+
+	/// Settings for specific apps of all hosts.
+	pub apps: Vec<AppConfig>,
+
+	/// Stream profiles by name, a separate shortcut is created for every app and profile.
+	pub profiles: BTreeMap<String, Profile>,
+
+	/// Hosts to retrieve apps from.
+	pub hosts: Vec<HostConfig>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct SteamConfig {
+	/// Path to the userdata directory of Steam, or the directory of a specific user.
+	pub userdata: Option<PathBuf>,
+
+	/// Account ID or persona name of the Steam user, used to pick a directory in `userdata`.
+	pub account: Option<String>,
+
+	/// What to do when Steam is running while the shortcuts file is written.
+	pub running: Option<RunningPolicy>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct SyncConfig {
+	/// Remove existing shortcuts of the synced hosts.
+	pub prune: bool,
+
+	/// Retrieve apps from all hosts that Moonlight is paired with.
+	pub all_paired: bool,
+
+	/// How to handle apps that are hidden in Moonlight, skipped by default.
+	pub hidden: Option<AppPolicy>,
+
+	/// How to handle apps that were added by an app collector, included by default.
+	pub app_collector: Option<AppPolicy>,
+
+	/// What happens to the app IDs of existing shortcuts when their executable or name changes.
+	pub app_ids: AppIdMode,
+}
+
+impl Default for SyncConfig {
+	fn default() -> Self {
+		Self {
+			prune: true,
+			all_paired: false,
+			hidden: None,
+			app_collector: None,
+			app_ids: AppIdMode::default(),
+		}
+	}
+}
+
+/// How to handle apps with a certain property, ordered from least to most strict.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum AppPolicy {
+	/// Create shortcuts like for any other app.
+	Include,
+
+	/// Create shortcuts that are hidden in the Steam library.
+	Hide,
+
+	/// Don't create shortcuts.
+	Skip,
+}
+
+impl std::fmt::Display for AppPolicy {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.write_str(match self {
+			Self::Include => "include",
+			Self::Hide => "hide",
+			Self::Skip => "skip",
+		})
+	}
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct BackupConfig {
+	/// Number of backups to keep, zero disables backups.
+	pub retention: usize,
+}
+
+impl Default for BackupConfig {
+	fn default() -> Self {
+		Self { retention: 10 }
+	}
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Filters {
+	/// If not empty, only create shortcuts for apps matching one of these patterns.
+	pub include: Vec<Pattern>,
+
+	/// Never create shortcuts for apps matching one of these patterns.
+	pub exclude: Vec<Pattern>,
+}
+
+impl Filters {
+	/// Returns the rule that excludes an app, or `None` if a shortcut should be created for it.
+	pub fn excluded_by(&self, app: &MoonlightApp) -> Option<String> {
+		if let Some(pattern) = self.exclude.iter().find(|p| p.matches(app)) {
+			return Some(format!("exclude '{pattern}'"));
+		}
+		if !self.include.is_empty() && !self.include.iter().any(|p| p.matches(app)) {
+			let patterns: Vec<String> = self.include.iter().map(|p| format!("'{p}'")).collect();
+			return Some(format!("include {}", patterns.join(", ")));
+		}
+
+		None
+	}
+}
+
+/// Pattern for apps: an exact title, `glob:<pattern>`, `regex:<pattern>` or `is:<property>` (like `is:hdr`).
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Pattern {
+	source: String,
+	matcher: Matcher,
+}
+
+#[derive(Debug, Clone)]
+enum Matcher {
+	Exact,
+	Regex(Regex),
+	Property(AppProperty),
+}
+
+impl Pattern {
+	pub fn matches(&self, app: &MoonlightApp) -> bool {
+		match &self.matcher {
+			Matcher::Exact => self.source == app.title,
+			Matcher::Regex(regex) => regex.is_match(&app.title),
+			Matcher::Property(property) => property.of(app),
+		}
+	}
+}
+
+impl FromStr for Pattern {
+	type Err = String;
+
+	fn from_str(source: &str) -> Result<Self, Self::Err> {
+		let regex = if let Some(glob) = source.strip_prefix("glob:") {
+			Some(glob_to_regex(glob))
+		} else {
+			source.strip_prefix("regex:").map(String::from)
+		};
+
+		let matcher = if let Some(regex) = regex {
+			Matcher::Regex(Regex::new(&regex).map_err(|e| format!("invalid pattern '{source}': {e}"))?)
+		} else if let Some(property) = source.strip_prefix("is:") {
+			Matcher::Property(property.parse().map_err(|e| format!("invalid pattern '{source}': {e}"))?)
+		} else {
+			Matcher::Exact
+		};
+
+		Ok(Self { source: source.to_string(), matcher })
+	}
+}
+
+impl TryFrom<String> for Pattern {
+	type Error = String;
+
+	fn try_from(source: String) -> Result<Self, Self::Error> {
+		source.parse()
+	}
+}
+
+impl std::fmt::Display for Pattern {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.write_str(&self.source)
+	}
+}
+
+/// Converts a glob (`*`, `?` and `[...]`) to an equivalent regular expression that matches whole titles.
+fn glob_to_regex(glob: &str) -> String {
+	let mut regex = String::from("^");
+	let mut chars = glob.chars();
+
+	while let Some(c) = chars.next() {
+		match c {
+			'*' => regex.push_str(".*"),
+			'?' => regex.push('.'),
+			'[' => {
+				regex.push('[');
+				let mut class = chars.by_ref().take_while(|c| *c != ']').peekable();
+				if class.next_if(|c| *c == '!').is_some() {
+					regex.push('^');
+				}
+				for c in class {
+					if c == '\\' || c == '[' {
+						regex.push('\\');
+					}
+					regex.push(c);
+				}
+				regex.push(']');
+			},
+			c => regex.push_str(&regex::escape(&c.to_string())),
+		}
+	}
+
+	regex.push('$');
+	regex
+}
+
+/// Rule that replaces the parts of a title matching a regular expression.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RenameRule {
+	#[serde(deserialize_with = "deserialize_regex")]
+	pub pattern: Regex,
+
+	/// Replacement for the matches, which can refer to capture groups like `$1`.
+	#[serde(default)]
+	pub replacement: String,
+}
+
+impl RenameRule {
+	pub fn apply(&self, title: &str) -> String {
+		self.pattern.replace_all(title, self.replacement.as_str()).into_owned()
+	}
+}
+
+fn deserialize_regex<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Regex, D::Error> {
+	let pattern = String::deserialize(deserializer)?;
+	Regex::new(&pattern).map_err(serde::de::Error::custom)
+}
+
+/// Placeholders that can be used in title templates and tags, besides the properties of apps like `{hdr}`.
+pub const PLACEHOLDERS: [&str; 4] = ["title", "host", "host_name", "id"];
+
+/// Values for the placeholders of title templates and tags.
+pub struct Placeholders<'a> {
+	/// Title of the app, after the rename rules.
+	pub title: &'a str,
+	pub host: &'a str,
+	pub host_name: &'a str,
+	pub app: &'a MoonlightApp,
+}
+
+impl Placeholders<'_> {
+	/// Returns the value of a placeholder, properties of the app are replaced by their label if the app has them.
+	fn value(&self, name: &str) -> Option<String> {
+		let value = match name {
+			"title" => self.title.to_string(),
+			"host" => self.host.to_string(),
+			"host_name" => self.host_name.to_string(),
+			"id" => self.app.id.map(|id| id.to_string()).unwrap_or_default(),
+			_ => {
+				let property: AppProperty = name.replace('_', "-").parse().ok()?;
+				if property.of(self.app) { property.label().to_string() } else { String::new() }
+			},
+		};
+		Some(value)
+	}
+
+	/// Fills in the placeholders of a template, leaving unknown placeholders as they are.
+	pub fn render(&self, template: &str) -> String {
+		let mut rendered = String::with_capacity(template.len());
+		let mut rest = template;
+		while let Some((before, after)) = rest.split_once('{') {
+			rendered.push_str(before);
+			match after.split_once('}').and_then(|(name, after)| Some((self.value(name)?, after))) {
+				Some((value, after)) => {
+					rendered.push_str(&value);
+					rest = after;
+				},
+				None => {
+					rendered.push('{');
+					rest = after;
+				},
+			}
+		}
+		rendered.push_str(rest);
+		rendered
+	}
+}
+
+/// Returns the names of all placeholders, including the properties of apps.
+fn placeholder_names() -> Vec<String> {
+	PLACEHOLDERS
+		.iter()
+		.map(|p| p.to_string())
+		.chain(AppProperty::ALL.iter().map(|p| p.name().replace('-', "_")))
+		.collect()
+}
+
+/// Options passed to `moonlight stream`, by the name of the option without dashes.
+///
+/// Booleans turn an option on (`--hdr`) or off (`--no-hdr`), other values are passed as the argument of the option.
+/// Names can be written with underscores as well, so `video_codec` is the same as `video-codec`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(from = "BTreeMap<String, StreamValue>")]
+pub struct StreamOptions {
+	options: BTreeMap<String, StreamValue>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum StreamValue {
+	Flag(bool),
+	Number(i64),
+	Text(String),
+}
+
+impl From<BTreeMap<String, StreamValue>> for StreamOptions {
+	fn from(options: BTreeMap<String, StreamValue>) -> Self {
+		Self { options: options.into_iter().map(|(name, value)| (name.replace('_', "-"), value)).collect() }
+	}
+}
+
+impl StreamOptions {
+	/// Returns these options, with the values that are set in `other` taking precedence.
+	pub fn merge(&self, other: &StreamOptions) -> StreamOptions {
+		let mut options = self.options.clone();
+		options.extend(other.options.clone());
+		StreamOptions { options }
+	}
+
+	/// Returns the flags of `moonlight stream` that these options use, like `--fps` or `--no-hdr`.
+	pub fn flags(&self) -> impl Iterator<Item = String> + '_ {
+		self.options.iter().map(|(name, value)| match value {
+			StreamValue::Flag(false) => format!("--no-{name}"),
+			_ => format!("--{name}"),
+		})
+	}
+
+	/// Converts the options to arguments for `moonlight stream`.
+	pub fn to_args(&self) -> Vec<String> {
+		let mut args = Vec::new();
+		for ((_, value), flag) in self.options.iter().zip(self.flags()) {
+			args.push(flag);
+			match value {
+				StreamValue::Flag(_) => {},
+				StreamValue::Number(number) => args.push(number.to_string()),
+				StreamValue::Text(text) => args.push(text.clone()),
+			}
+		}
+		args
+	}
+}
+
+/// Stream option given on the command line, as `name=value`, `name` or `no-name`.
+#[derive(Debug, Clone)]
+pub struct StreamOption(String, StreamValue);
+
+impl FromStr for StreamOption {
+	type Err = String;
+
+	fn from_str(option: &str) -> Result<Self, Self::Err> {
+		let option = option.trim_start_matches('-');
+		let (name, value) = match option.split_once('=') {
+			Some((name, value)) => match value.parse() {
+				Ok(number) => (name, StreamValue::Number(number)),
+				Err(_) => (name, StreamValue::Text(value.to_string())),
+			},
+			None => match option.strip_prefix("no-") {
+				Some(name) => (name, StreamValue::Flag(false)),
+				None => (option, StreamValue::Flag(true)),
+			},
+		};
+
+		if name.is_empty() {
+			return Err("name of the stream option is missing".to_string());
+		}
+		Ok(Self(name.to_string(), value))
+	}
+}
+
+impl FromIterator<StreamOption> for StreamOptions {
+	fn from_iter<T: IntoIterator<Item = StreamOption>>(options: T) -> Self {
+		options.into_iter().map(|StreamOption(name, value)| (name, value)).collect::<BTreeMap<_, _>>().into()
+	}
+}
+
+/// Variant of the shortcuts of apps, with its own stream options.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Profile {
+	/// Appended to the names of the shortcuts of this profile, defaults to ` (<name of the profile>)`.
+	pub suffix: Option<String>,
+
+	/// Stream options for this profile, overriding the options of hosts and apps.
+	pub stream: StreamOptions,
+}
+
+impl Profile {
+	/// Returns the suffix for the names of the shortcuts of the profile with the given name.
+	pub fn suffix(&self, name: &str) -> String {
+		self.suffix.clone().unwrap_or_else(|| format!(" ({name})"))
+	}
+}
+
+/// Settings for the apps that match a pattern.
+#[derive(Debug, Clone, Deserialize)]
+pub struct AppConfig {
+	/// Title of the app, or a pattern for it.
+	pub title: Pattern,
+
+	/// Stream options for the app, overriding those of the host.
+	#[serde(default)]
+	pub stream: StreamOptions,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct HostConfig {
+	/// Address or name of the host.
+	pub address: String,
+
+	/// Extra tags to add to the shortcuts of this host, which can contain placeholders like the global tags.
+	pub tags: Vec<String>,
+
+	/// Template for the names of the shortcuts of this host, overriding the global template.
+	pub title_template: Option<String>,
+
+	/// Rules that rename the titles of the apps of this host, applied after the global rules.
+	pub rename: Vec<RenameRule>,
+
+	/// Filters for the apps of this host, applied in addition to the global filters.
+	pub filters: Filters,
+
+	/// Stream options for this host, overriding the global stream options.
+	pub stream: StreamOptions,
+
+	/// Settings for specific apps of this host, overriding the global settings for apps.
+	pub apps: Vec<AppConfig>,
+
+	/// Names of the profiles to create shortcuts for, instead of all profiles.
+	pub profiles: Option<Vec<String>>,
+}
+
+impl Config {
+	/// Returns the stream options for an app of a host, from least to most specific: global, host, app, host app
+	/// and profile.
+	pub fn stream_options(&self, host: &HostConfig, app: &MoonlightApp, profile: Option<&Profile>) -> StreamOptions {
+		let app_options = self.apps.iter().chain(&host.apps).filter(|a| a.title.matches(app)).map(|a| &a.stream);
+		[&self.stream, &host.stream]
+			.into_iter()
+			.chain(app_options)
+			.chain(profile.map(|p| &p.stream))
+			.fold(StreamOptions::default(), |o, other| o.merge(other))
+	}
+
+	/// Returns the profiles to create shortcuts for on a host, which is empty if no profiles are used.
+	pub fn host_profiles(&self, host: &HostConfig) -> Vec<(&str, &Profile)> {
+		self.profiles
+			.iter()
+			.filter(|(name, _)| host.profiles.as_ref().is_none_or(|p| p.contains(name)))
+			.map(|(name, profile)| (name.as_str(), profile))
+			.collect()
+	}
+
+	/// Returns all stream options in the configuration, with the path to where they are set.
+	pub fn all_stream_options(&self) -> Vec<(String, &StreamOptions)> {
+		let mut options = vec![("stream".to_string(), &self.stream)];
+		options.extend(self.apps.iter().enumerate().map(|(i, a)| (format!("apps.{i}.stream"), &a.stream)));
+		options.extend(self.profiles.iter().map(|(name, p)| (format!("profiles.{name}.stream"), &p.stream)));
+		for (i, host) in self.hosts.iter().enumerate() {
+			options.push((format!("hosts.{i}.stream"), &host.stream));
+			options.extend(host.apps.iter().enumerate().map(|(j, a)| (format!("hosts.{i}.apps.{j}.stream"), &a.stream)));
+		}
+		options
+	}
+}
+
+/// A key in the configuration file that isn't recognized.
+#[derive(Debug)]
+pub struct UnknownKey {
+	/// Dotted path to the key, for example `hosts.0.adress`.
+	pub path: String,
+
+	/// Line (1-based) on which the key is defined.
+	pub line: Option<usize>,
+}
+
+impl std::fmt::Display for UnknownKey {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self.line {
+			Some(line) => write!(f, "unknown key '{}' on line {line}", self.path),
+			None => write!(f, "unknown key '{}'", self.path),
+		}
+	}
+}
+
+/// Returns the default location of the configuration file.
+pub fn default_path() -> Option<PathBuf> {
+	xdg::BaseDirectories::with_prefix(APP_NAME).ok().map(|d| d.get_config_file("config.toml"))
+}
+
+/// Loads the configuration from `path`, or from the default location if no path is given.
+///
+/// A missing configuration file at the default location results in the default configuration.
+pub fn load(path: Option<&Path>) -> Result<(Config, Vec<UnknownKey>), String> {
+	let path = match path {
+		Some(path) => path.to_path_buf(),
+		None => match default_path() {
+			Some(path) if path.exists() => path,
+			_ => return Ok((Config::default(), Vec::new())),
+		},
+	};
+
+	let contents = std::fs::read_to_string(&path)
+		.map_err(|e| format!("Failed to read configuration file '{}': {e}", path.display()))?;
+
+	parse(&contents).map_err(|e| format!("Failed to parse configuration file '{}': {e}", path.display()))
+}
+
+/// Parses a configuration, returning the keys that were not recognized alongside it.
+pub fn parse(contents: &str) -> Result<(Config, Vec<UnknownKey>), String> {
+	let deserializer = toml::de::Deserializer::parse(contents).map_err(|e| e.to_string())?;
+
+	let mut unknown_paths = Vec::new();
+	let config: Config = serde_ignored::deserialize(deserializer, |path| unknown_paths.push(path_segments(&path)))
+		.map_err(|e| e.to_string())?;
+
+	let table = DeTable::parse(contents).map_err(|e| e.to_string())?;
+	let mut unknown_keys: Vec<UnknownKey> = unknown_paths
+		.into_iter()
+		.map(|segments| UnknownKey {
+			path: segments.join("."),
+			line: key_offset(table.get_ref(), &segments).map(|offset| contents[..offset].matches('\n').count() + 1),
+		})
+		.collect();
+	unknown_keys.sort_by_key(|k| k.line);
+
+	Ok((config, unknown_keys))
+}
+
+/// Validates a configuration, returning a list of problems.
+pub fn validate(config: &Config) -> Vec<String> {
+	let mut problems = Vec::new();
+
+	if let Some(moonlight) = &config.moonlight {
+		if !moonlight.is_file() {
+			problems.push(format!("moonlight: '{}' does not exist or is not a file", moonlight.display()));
+		}
+	}
+
+	if let Some(userdata) = &config.steam.userdata {
+		if !userdata.is_dir() {
+			problems.push(format!("steam.userdata: '{}' does not exist or is not a directory", userdata.display()));
+		}
+	}
+
+	let templates = std::iter::once(("title_template".to_string(), &config.title_template))
+		.chain(config.hosts.iter().enumerate().map(|(i, h)| (format!("hosts.{i}.title_template"), &h.title_template)));
+	for (path, template) in templates {
+		if let Some(template) = template {
+			problems.extend(validate_template(template).into_iter().map(|p| format!("{path}: {p}")));
+		}
+	}
+
+	let mut tags: Vec<(String, &String)> = config.tags.iter().enumerate().map(|(i, t)| (format!("tags.{i}"), t)).collect();
+	for (i, host) in config.hosts.iter().enumerate() {
+		tags.extend(host.tags.iter().enumerate().map(|(j, t)| (format!("hosts.{i}.tags.{j}"), t)));
+	}
+	for (path, tag) in tags {
+		problems.extend(validate_placeholders(tag).into_iter().map(|p| format!("{path}: {p}")));
+	}
+
+	for (i, host) in config.hosts.iter().enumerate() {
+		if host.address.trim().is_empty() {
+			problems.push(format!("hosts.{i}.address: address of the host is missing"));
+		}
+		for profile in host.profiles.iter().flatten().filter(|p| !config.profiles.contains_key(*p)) {
+			problems.push(format!("hosts.{i}.profiles: profile '{profile}' is not defined"));
+		}
+	}
+
+	problems
+}
+
+fn validate_template(template: &str) -> Vec<String> {
+	let mut problems = Vec::new();
+
+	if !template.contains("{title}") {
+		problems.push("template doesn't contain '{title}', so all shortcuts of a host get the same name".to_string());
+	}
+	problems.extend(validate_placeholders(template));
+
+	problems
+}
+
+fn validate_placeholders(template: &str) -> Vec<String> {
+	let names = placeholder_names();
+	let mut problems = Vec::new();
+
+	let mut rest = template;
+	while let Some((_, after)) = rest.split_once('{') {
+		let Some((name, after)) = after.split_once('}') else {
+			break;
+		};
+		if !names.iter().any(|n| n == name) {
+			problems.push(format!("unknown placeholder '{{{name}}}', expected one of {}", names.join(", ")));
+		}
+		rest = after;
+	}
+
+	problems
+}
+
+fn path_segments(path: &serde_ignored::Path) -> Vec<String> {
+	let mut segments = match path {
+		serde_ignored::Path::Root => return Vec::new(),
+		serde_ignored::Path::Seq { parent, .. }
+		| serde_ignored::Path::Map { parent, .. }
+		| serde_ignored::Path::Some { parent }
+		| serde_ignored::Path::NewtypeStruct { parent }
+		| serde_ignored::Path::NewtypeVariant { parent } => path_segments(parent),
+	};
+
+	match path {
+		serde_ignored::Path::Seq { index, .. } => segments.push(index.to_string()),
+		serde_ignored::Path::Map { key, .. } => segments.push(key.clone()),
+		_ => {},
+	}
+
+	segments
+}
+
+/// Finds the byte offset of the key at the given path in the parsed document.
+fn key_offset(table: &DeTable, segments: &[String]) -> Option<usize> {
+	let (first, rest) = segments.split_first()?;
+	let (key, value) = table.iter().find(|(k, _)| k.get_ref() == first)?;
+	if rest.is_empty() {
+		return Some(key.span().start);
+	}
+
+	value_offset(value, rest)
+}
+
+fn value_offset(value: &Spanned<DeValue>, segments: &[String]) -> Option<usize> {
+	match value.get_ref() {
+		DeValue::Table(table) => key_offset(table, segments),
+		DeValue::Array(array) => {
+			let (index, rest) = segments.split_first()?;
+			let item = array.get(index.parse::<usize>().ok()?)?;
+			if rest.is_empty() {
+				return Some(item.span().start);
+			}
+			value_offset(item, rest)
+		},
+		_ => None,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn app(title: &str) -> MoonlightApp {
+		MoonlightApp {
+			title: title.to_string(),
+			id: Some(1),
+			boxart: None,
+			hdr_supported: false,
+			app_collector: false,
+			hidden: false,
+			direct_launch: false,
+		}
+	}
+
+	fn matches(pattern: &str, title: &str) -> bool {
+		pattern.parse::<Pattern>().unwrap().matches(&app(title))
+	}
+
+	fn unknown_keys(contents: &str) -> Vec<(String, Option<usize>)> {
+		let (_, unknown) = parse(contents).unwrap();
+		unknown.into_iter().map(|k| (k.path, k.line)).collect()
+	}
+
+	#[test]
+	fn reports_unknown_keys_with_lines() {
+		let contents = r#"moonligt = "/usr/bin/moonlight"
+tags = ["moonlight"]
+
+[sync]
+prune = true
+prun = false
+
+[profiles.hdr]
+suffix = " (HDR)"
+sufix = " HDR"
+
+[[hosts]]
+address = "10.0.0.7"
+
+[[hosts]]
+adress = "10.0.0.8"
+"#;
+
+		assert_eq!(
+			unknown_keys(contents),
+			[
+				("moonligt".to_string(), Some(1)),
+				("sync.prun".to_string(), Some(6)),
+				("profiles.hdr.sufix".to_string(), Some(10)),
+				("hosts.1.adress".to_string(), Some(16)),
+			]
+		);
+	}
+
+	#[test]
+	fn reports_unknown_keys_in_inline_tables() {
+		let contents = "hosts = [{ address = \"10.0.0.7\" }, { address = \"10.0.0.8\", tag = \"x\" }]\n\
+			sync = { prune = true, all_pared = true }\n";
+
+		assert_eq!(
+			unknown_keys(contents),
+			[("hosts.1.tag".to_string(), Some(1)), ("sync.all_pared".to_string(), Some(2))]
+		);
+	}
+
+	#[test]
+	fn accepts_known_keys() {
+		let (config, unknown) = parse(
+			r#"title_template = "{title} on {host_name}"
+
+[stream]
+fps = 60
+
+[[hosts]]
+address = "10.0.0.7"
+profiles = ["hdr"]
+
+[profiles.hdr.stream]
+hdr = true
+"#,
+		)
+		.unwrap();
+
+		assert!(unknown.is_empty());
+		assert_eq!(config.hosts[0].address, "10.0.0.7");
+		assert!(validate(&config).is_empty());
+	}
+
+	#[test]
+	fn reports_invalid_values() {
+		assert!(parse("sync = { prune = \"yes\" }").is_err());
+		assert!(parse("[filters]\ninclude = [\"regex:(\"]").is_err());
+	}
+
+	#[test]
+	fn validates_configuration() {
+		let (config, _) = parse(
+			r#"moonlight = "/nonexistent/moonlight"
+tags = ["{title}", "{nope}"]
+title_template = "Moonlight"
+
+[steam]
+userdata = "/nonexistent/userdata"
+
+[[hosts]]
+address = " "
+tags = ["{host}-{hdr}", "{bad}"]
+title_template = "{title} {unknown}"
+profiles = ["hdr", "missing"]
+
+[profiles.hdr]
+"#,
+		)
+		.unwrap();
+
+		let problems = validate(&config);
+		let expected = [
+			"moonlight: '/nonexistent/moonlight' does not exist or is not a file",
+			"steam.userdata: '/nonexistent/userdata' does not exist or is not a directory",
+			"title_template: template doesn't contain '{title}', so all shortcuts of a host get the same name",
+			"hosts.0.title_template: unknown placeholder '{unknown}'",
+			"tags.1: unknown placeholder '{nope}'",
+			"hosts.0.tags.1: unknown placeholder '{bad}'",
+			"hosts.0.address: address of the host is missing",
+			"hosts.0.profiles: profile 'missing' is not defined",
+		];
+		assert_eq!(problems.len(), expected.len(), "{problems:#?}");
+		for (problem, expected) in problems.iter().zip(expected) {
+			assert!(problem.starts_with(expected), "'{problem}' doesn't start with '{expected}'");
+		}
+	}
+
+	#[test]
+	fn matches_exact_titles() {
+		assert!(matches("Desktop", "Desktop"));
+		assert!(!matches("Desktop", "desktop"));
+		assert!(!matches("Desk", "Desktop"));
+	}
+
+	#[test]
+	fn matches_globs() {
+		assert!(matches("glob:Steam*", "Steam Big Picture"));
+		assert!(matches("glob:*", ""));
+		assert!(!matches("glob:Steam*", "My Steam"));
+		assert!(matches("glob:Game ?", "Game 2"));
+		assert!(!matches("glob:Game ?", "Game 10"));
+		assert!(matches("glob:Game [123]", "Game 2"));
+		assert!(!matches("glob:Game [123]", "Game 4"));
+		assert!(matches("glob:Game [0-9]", "Game 7"));
+		assert!(matches("glob:Game [!0-9]", "Game X"));
+		assert!(!matches("glob:Game [!0-9]", "Game 7"));
+	}
+
+	#[test]
+	fn escapes_regex_characters_in_globs() {
+		assert_eq!(glob_to_regex("a.b+(c)|$^{1}"), r"^a\.b\+\(c\)\|\$\^\{1\}$");
+		assert!(matches("glob:Cyberpunk (2077).exe", "Cyberpunk (2077).exe"));
+		assert!(!matches("glob:Cyberpunk (2077).exe", "Cyberpunk (2077)-exe"));
+		assert!(matches(r"glob:[\[]*", "[Beta] Game"));
+	}
+
+	#[test]
+	fn matches_regexes_and_properties() {
+		assert!(matches("regex:^(Desktop|Steam)", "Steam Big Picture"));
+		assert!(matches("regex:Big", "Steam Big Picture"));
+		assert!(!matches("regex:^Big", "Steam Big Picture"));
+
+		let pattern: Pattern = "is:hdr".parse().unwrap();
+		let mut hdr = app("Cyberpunk");
+		assert!(!pattern.matches(&hdr));
+		hdr.hdr_supported = true;
+		assert!(pattern.matches(&hdr));
+		let collector = MoonlightApp { app_collector: true, ..app("Steam") };
+		assert!("is:app-collector".parse::<Pattern>().unwrap().matches(&collector));
+	}
+
+	#[test]
+	fn rejects_invalid_patterns() {
+		let error = "regex:(".parse::<Pattern>().unwrap_err();
+		assert!(error.starts_with("invalid pattern 'regex:('"), "{error}");
+		assert!("is:4k".parse::<Pattern>().is_err());
+		assert!("glob:[".parse::<Pattern>().is_err());
+	}
+
+	#[test]
+	fn reports_the_filter_that_excludes_an_app() {
+		let filters = Filters {
+			include: vec!["glob:Steam*".parse().unwrap(), "Desktop".parse().unwrap()],
+			exclude: vec!["regex:VR$".parse().unwrap()],
+		};
+
+		assert_eq!(filters.excluded_by(&app("Desktop")), None);
+		assert_eq!(filters.excluded_by(&app("Steam VR")).as_deref(), Some("exclude 'regex:VR$'"));
+		assert_eq!(filters.excluded_by(&app("Cyberpunk")).as_deref(), Some("include 'glob:Steam*', 'Desktop'"));
+		assert_eq!(Filters::default().excluded_by(&app("Cyberpunk")), None);
+	}
+
+	fn stream_options(options: &[&str]) -> StreamOptions {
+		options.iter().map(|o| o.parse::<StreamOption>().unwrap()).collect()
+	}
+
+	#[test]
+	fn overrides_stream_options_from_global_to_command_line() {
+		let contents = r#"[stream]
+fps = 30
+bitrate = 10000
+video_codec = "H.264"
+hdr = true
+vsync = true
+
+[[apps]]
+title = "glob:Cyber*"
+stream = { fps = 90, bitrate = 20000 }
+
+[profiles.low]
+stream = { bitrate = 5000, hdr = false }
+
+[[hosts]]
+address = "10.0.0.7"
+stream = { fps = 60, video-codec = "HEVC" }
+apps = [{ title = "Cyberpunk", stream = { fps = 120 } }]
+"#;
+		let (config, _) = parse(contents).unwrap();
+		let host = &config.hosts[0];
+		let profile = &config.profiles["low"];
+
+		let desktop = config.stream_options(host, &app("Desktop"), None);
+		assert_eq!(
+			desktop.to_args(),
+			["--bitrate", "10000", "--fps", "60", "--hdr", "--video-codec", "HEVC", "--vsync"]
+		);
+
+		let cyberpunk = config.stream_options(host, &app("Cyberpunk"), None);
+		assert_eq!(
+			cyberpunk.to_args(),
+			["--bitrate", "20000", "--fps", "120", "--hdr", "--video-codec", "HEVC", "--vsync"]
+		);
+
+		let low = config.stream_options(host, &app("Cyberpunk"), Some(profile));
+		assert_eq!(
+			low.to_args(),
+			["--bitrate", "5000", "--fps", "120", "--no-hdr", "--video-codec", "HEVC", "--vsync"]
+		);
+
+		let cli = low.merge(&stream_options(&["--fps=144", "no-vsync"]));
+		assert_eq!(
+			cli.to_args(),
+			["--bitrate", "5000", "--fps", "144", "--no-hdr", "--video-codec", "HEVC", "--no-vsync"]
+		);
+		assert_eq!(cli.flags().collect::<Vec<_>>(), ["--bitrate", "--fps", "--no-hdr", "--video-codec", "--no-vsync"]);
+	}
+
+	#[test]
+	fn parses_stream_options_of_the_command_line() {
+		let option = |option: &str| option.parse::<StreamOption>().map(|StreamOption(name, value)| (name, value));
+		let text = |text: &str| StreamValue::Text(text.to_string());
+
+		assert_eq!(option("fps=60"), Ok(("fps".to_string(), StreamValue::Number(60))));
+		assert_eq!(option("--video-codec=HEVC"), Ok(("video-codec".to_string(), text("HEVC"))));
+		assert_eq!(option("resolution=1920x1080"), Ok(("resolution".to_string(), text("1920x1080"))));
+		assert_eq!(option("app-name="), Ok(("app-name".to_string(), text(""))));
+		assert_eq!(option("hdr"), Ok(("hdr".to_string(), StreamValue::Flag(true))));
+		assert_eq!(option("--no-hdr"), Ok(("hdr".to_string(), StreamValue::Flag(false))));
+		assert!(option("--").is_err());
+		assert!(option("=60").is_err());
+
+		// The last value given for an option wins, and underscores are the same as dashes.
+		let options = stream_options(&["fps=30", "video_codec=AV1", "fps=60"]);
+		assert_eq!(options.to_args(), ["--fps", "60", "--video-codec", "AV1"]);
+	}
+
+	#[test]
+	fn renders_placeholders() {
+		let mut cyberpunk = app("Cyberpunk");
+		cyberpunk.hdr_supported = true;
+		let placeholders =
+			Placeholders { title: "Cyberpunk 2077", host: "10.0.0.7", host_name: "gaming-pc", app: &cyberpunk };
+
+		assert_eq!(placeholders.render("{title} on {host_name} ({host})"), "Cyberpunk 2077 on gaming-pc (10.0.0.7)");
+		assert_eq!(placeholders.render("{title} #{id} {hdr}{direct_launch}"), "Cyberpunk 2077 #1 HDR");
+		assert_eq!(placeholders.render("{title} [{app_collector}]"), "Cyberpunk 2077 []");
+		assert_eq!(placeholders.render("{titel} {title} {"), "{titel} Cyberpunk 2077 {");
+		assert_eq!(placeholders.render("{{title}}"), "{Cyberpunk 2077}");
+		assert_eq!(placeholders.render("no placeholders"), "no placeholders");
+
+		let desktop = MoonlightApp { id: None, ..app("Desktop") };
+		let placeholders = Placeholders { title: "", host: "10.0.0.7", host_name: "", app: &desktop };
+		assert_eq!(placeholders.render("[{title}] {host_name}/{id}"), "[] /");
+	}
+
+	#[test]
+	fn reports_unknown_placeholders() {
+		assert!(validate_placeholders("{title} {host} {host_name} {id} {hdr} {app_collector} {hidden}").is_empty());
+		assert!(validate_placeholders("{direct_launch} {title").is_empty());
+
+		let problems = validate_placeholders("{titel} ({host}) {app-collector}");
+		assert_eq!(problems.len(), 2, "{problems:?}");
+		let expected = "unknown placeholder '{titel}', expected one of title, host, host_name, id, hdr, app_collector";
+		assert!(problems[0].starts_with(expected), "{problems:?}");
+		assert!(problems[1].starts_with("unknown placeholder '{app-collector}'"));
+	}
+
+	#[test]
+	fn applies_rename_rules_in_order() {
+		let contents = r#"[[rename]]
+pattern = "^The (.*)$"
+replacement = "$1, The"
+
+[[rename]]
+pattern = "\\s*\\(\\d{4}\\)"
+
+[[rename]]
+pattern = "Witcher"
+replacement = "Witcher 3"
+"#;
+		let (config, _) = parse(contents).unwrap();
+		let rename = |title: &str| config.rename.iter().fold(title.to_string(), |title, rule| rule.apply(&title));
+
+		assert_eq!(rename("The Witcher (2015)"), "Witcher 3, The");
+		assert_eq!(rename("Cyberpunk 2077 (2020) (2023)"), "Cyberpunk 2077");
+		assert_eq!(rename("Desktop"), "Desktop");
+		assert_eq!(config.rename[1].apply("Game (1999)"), "Game");
+	}
+}
@@ -0,0 +1,623 @@
+use std::{
+	io::{ErrorKind, Read, Write},
+	net::{TcpStream, ToSocketAddrs},
+	sync::Arc,
+	time::Duration,
+};
+
+use rustls::{
+	client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+	crypto::{ring, verify_tls12_signature, verify_tls13_signature, CryptoProvider},
+	pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer, ServerName, UnixTime},
+	ClientConfig,
+	ClientConnection,
+	DigitallySignedStruct,
+	RootCertStore,
+	SignatureScheme,
+	StreamOwned,
+};
+
+use crate::{apps::{AppSource, MoonlightApp}, boxart, moonlight_conf, output::info, pairing};
+
+/// Port on which hosts serve plain HTTP requests.
+pub const DEFAULT_HTTP_PORT: u16 = 47989;
+
+/// Port on which hosts serve HTTPS requests, unless the host reports otherwise.
+pub const DEFAULT_HTTPS_PORT: u16 = 47984;
+
+/// Unique ID that Moonlight sends with every request, hosts identify clients by their certificate instead.
+pub const UNIQUE_ID: &str = "0123456789ABCDEF";
+
+/// How long to wait for a host to respond.
+pub const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Certificate and private key (both PEM) that a client uses to identify itself to hosts.
+#[derive(Debug, Clone)]
+pub struct ClientIdentity {
+	pub certificate: String,
+	pub key: String,
+}
+
+/// Information that a host reports about itself.
+#[derive(Debug, Clone)]
+pub struct ServerInfo {
+	/// Name of the host.
+	pub hostname: String,
+
+	/// Unique identifier of the host.
+	pub uuid: String,
+
+	/// Version of the GameStream protocol that the host implements, for example "7.1.431.-1".
+	pub app_version: String,
+
+	/// Port on which the host serves HTTPS requests.
+	pub https_port: u16,
+
+	/// Whether the host considers the client that made the request paired.
+	pub paired: bool,
+}
+
+/// Splits an address like `host`, `host:port`, `[::1]:port` or `::1` in a host and an optional port.
+pub fn split_address(address: &str) -> (String, Option<u16>) {
+	if let Some(rest) = address.strip_prefix('[') {
+		if let Some((host, port)) = rest.split_once(']') {
+			return (host.to_string(), port.strip_prefix(':').and_then(|p| p.parse().ok()));
+		}
+	}
+
+	match address.rsplit_once(':') {
+		// More than one colon without brackets means this is a bare IPv6 address.
+		Some((host, port)) if !host.contains(':') => match port.parse() {
+			Ok(port) => (host.to_string(), Some(port)),
+			Err(_) => (address.to_string(), None),
+		},
+		_ => (address.to_string(), None),
+	}
+}
+
+/// Performs a plain HTTP GET request, which hosts only allow for requests that don't need authentication.
+pub fn http_get(host: &str, port: u16, path: &str, timeout: Duration) -> Result<Vec<u8>, String> {
+	let stream = connect(host, port, timeout)?;
+	request(stream, host, path)
+}
+
+/// Performs a GET request over HTTPS to a server with a certificate from a public certificate authority.
+pub fn https_get(host: &str, port: u16, path: &str) -> Result<Vec<u8>, String> {
+	let roots = RootCertStore { roots: webpki_roots::TLS_SERVER_ROOTS.to_vec() };
+	let tls_config = ClientConfig::builder_with_provider(Arc::new(ring::default_provider()))
+		.with_safe_default_protocol_versions()
+		.map_err(|e| format!("Failed to configure TLS: {e}"))?
+		.with_root_certificates(roots)
+		.with_no_client_auth();
+
+	let server_name = ServerName::try_from(host.to_string()).map_err(|e| format!("Invalid server name '{host}': {e}"))?;
+	let connection = ClientConnection::new(Arc::new(tls_config), server_name)
+		.map_err(|e| format!("Failed to set up TLS connection: {e}"))?;
+
+	let stream = StreamOwned::new(connection, connect(host, port, TIMEOUT)?);
+	request(stream, host, path)
+}
+
+/// Retrieves the server info of a host over plain HTTP.
+///
+/// Hosts never report the client as paired in this case, use [`Connection::server_info`] for that.
+pub fn http_server_info(host: &str, port: u16) -> Result<ServerInfo, String> {
+	let body = http_get(host, port, &format!("/serverinfo?uniqueid={UNIQUE_ID}"), TIMEOUT)?;
+	parse_server_info(&body)
+}
+
+/// Authenticated connection to a host over HTTPS.
+pub struct Connection {
+	host: String,
+	port: u16,
+	tls_config: Arc<ClientConfig>,
+}
+
+impl Connection {
+	/// Creates a connection that identifies with `identity` and only trusts `server_certificate` (PEM).
+	pub fn new(host: &str, port: u16, identity: &ClientIdentity, server_certificate: &str) -> Result<Self, String> {
+		let client_certificate = CertificateDer::from_pem_slice(identity.certificate.as_bytes())
+			.map_err(|e| format!("Failed to parse client certificate: {e}"))?;
+		let client_key = PrivateKeyDer::from_pem_slice(identity.key.as_bytes())
+			.map_err(|e| format!("Failed to parse client key: {e}"))?;
+		let server_certificate = CertificateDer::from_pem_slice(server_certificate.as_bytes())
+			.map_err(|e| format!("Failed to parse server certificate: {e}"))?;
+
+		let provider = Arc::new(ring::default_provider());
+		let tls_config = ClientConfig::builder_with_provider(provider.clone())
+			.with_safe_default_protocol_versions()
+			.map_err(|e| format!("Failed to configure TLS: {e}"))?
+			.dangerous()
+			.with_custom_certificate_verifier(Arc::new(PinnedCertificate { certificate: server_certificate, provider }))
+			.with_client_auth_cert(vec![client_certificate], client_key)
+			.map_err(|e| format!("Failed to use client certificate: {e}"))?;
+
+		Ok(Self {
+			host: host.to_string(),
+			port,
+			tls_config: Arc::new(tls_config),
+		})
+	}
+
+	/// Performs a GET request over HTTPS.
+	pub fn get(&self, path: &str) -> Result<Vec<u8>, String> {
+		// The certificate of the host is pinned, so the server name only matters for SNI.
+		let server_name = ServerName::try_from(self.host.clone())
+			.unwrap_or_else(|_| ServerName::try_from("gamestream").unwrap());
+		let connection = ClientConnection::new(self.tls_config.clone(), server_name)
+			.map_err(|e| format!("Failed to set up TLS connection: {e}"))?;
+
+		let stream = StreamOwned::new(connection, connect(&self.host, self.port, TIMEOUT)?);
+		request(stream, &self.host, path)
+	}
+
+	/// Retrieves the server info of the host.
+	pub fn server_info(&self) -> Result<ServerInfo, String> {
+		parse_server_info(&self.get(&format!("/serverinfo?uniqueid={UNIQUE_ID}"))?)
+	}
+
+	/// Retrieves the apps that the host provides, without their boxart.
+	pub fn app_list(&self) -> Result<Vec<MoonlightApp>, String> {
+		let body = self.get(&format!("/applist?uniqueid={UNIQUE_ID}"))?;
+		let text = String::from_utf8_lossy(&body);
+		let document = parse_response(&text)?;
+
+		let apps = document
+			.root_element()
+			.children()
+			.filter(|n| n.has_tag_name("App"))
+			.map(|app| MoonlightApp {
+				title: child_text(app, "AppTitle").unwrap_or_default(),
+				id: child_text(app, "ID").and_then(|id| id.parse().ok()),
+				boxart: None,
+				hdr_supported: child_text(app, "IsHdrSupported").is_some_and(|v| v == "1"),
+				app_collector: child_text(app, "IsAppCollectorGame").is_some_and(|v| v == "1"),
+				// These are settings of Moonlight, which the host doesn't know about, see `apply_app_settings`.
+				hidden: false,
+				direct_launch: false,
+			})
+			.filter(|app| !app.title.is_empty())
+			.collect();
+
+		Ok(apps)
+	}
+
+	/// Retrieves the boxart (PNG) of an app.
+	pub fn app_asset(&self, app_id: u32) -> Result<Vec<u8>, String> {
+		self.get(&format!("/appasset?uniqueid={UNIQUE_ID}&appid={app_id}&AssetType=2&AssetIdx=0"))
+	}
+}
+
+/// Retrieves apps by talking to the host directly.
+///
+/// Hosts that were paired using this tool are preferred, otherwise the hosts and client certificate of Moonlight are used.
+/// Whether apps are hidden or launched directly is read from the configuration of Moonlight, if it knows the host.
+pub struct NativeClient;
+
+impl NativeClient {
+	/// Sets up an authenticated connection to a host, returning it together with the UUID of the host.
+	fn connect(&self, host: &str) -> Result<(Connection, String), String> {
+		let (identity, server_certificate, address, uuid) = match pairing::find_host(host)? {
+			Some(paired) => {
+				let identity = pairing::load_identity()?
+					.ok_or_else(|| "Client identity is missing, pair with the host again.".to_string())?;
+				// Use the given address when possible, since that is what the user expects to connect to.
+				let address = if paired.name.eq_ignore_ascii_case(host) || paired.uuid == host { paired.address } else { host.to_string() };
+				(identity, paired.certificate, address, paired.uuid)
+			},
+			None => {
+				let known_host = moonlight_conf::find_hosts()?
+					.into_iter()
+					.find(|h| h.matches(host))
+					.ok_or_else(|| format!("Host '{host}' is not paired."))?;
+				let server_certificate = known_host.server_certificate
+					.clone()
+					.ok_or_else(|| format!("Moonlight is not paired with '{host}'."))?;
+
+				let address = if known_host.addresses().any(|a| a == host) {
+					host
+				} else {
+					known_host.address().ok_or_else(|| format!("No address known for '{host}'."))?
+				};
+				(moonlight_conf::find_client_identity()?, server_certificate, address.to_string(), known_host.uuid)
+			},
+		};
+
+		let (address, http_port) = split_address(&address);
+		let server_info = http_server_info(&address, http_port.unwrap_or(DEFAULT_HTTP_PORT))?;
+		let connection = Connection::new(&address, server_info.https_port, &identity, &server_certificate)?;
+
+		Ok((connection, uuid))
+	}
+
+	/// Whether the host is paired, either with this tool or with Moonlight.
+	pub fn is_paired(&self, host: &str) -> Result<bool, String> {
+		let known = pairing::find_host(host)?.is_some()
+			|| moonlight_conf::find_hosts().is_ok_and(|hosts| hosts.iter().any(|h| h.paired && h.matches(host)));
+		if !known {
+			return Ok(false);
+		}
+
+		Ok(self.connect(host)?.0.server_info()?.paired)
+	}
+}
+
+impl AppSource for NativeClient {
+	fn apps(&self, host: &str) -> Result<Vec<MoonlightApp>, String> {
+		let (connection, uuid) = self.connect(host)?;
+
+		if !connection.server_info()?.paired {
+			return Err(format!("'{host}' doesn't consider this client to be paired."));
+		}
+
+		let mut apps = connection.app_list()?;
+		// Hosts paired with this tool alone have no settings in Moonlight, so its configuration is optional.
+		let settings = moonlight_conf::find_hosts()
+			.unwrap_or_default()
+			.into_iter()
+			.find(|h| h.uuid == uuid)
+			.map(|h| h.apps)
+			.unwrap_or_default();
+		apply_app_settings(&mut apps, &settings);
+		for app in &mut apps {
+			let Some(id) = app.id else {
+				continue;
+			};
+
+			match connection.app_asset(id).and_then(|image| boxart::store(host, id, &image)) {
+				Ok(path) => app.boxart = Some(path),
+				Err(e) => info!("Failed to retrieve boxart of '{}': {e}", app.title),
+			}
+		}
+
+		Ok(apps)
+	}
+}
+
+/// Copies the settings that Moonlight keeps for apps to the apps with the same ID.
+fn apply_app_settings(apps: &mut [MoonlightApp], settings: &[moonlight_conf::AppSettings]) {
+	for app in apps {
+		if let Some(settings) = settings.iter().find(|s| Some(s.id) == app.id) {
+			app.hidden = settings.hidden;
+			app.direct_launch = settings.direct_launch;
+		}
+	}
+}
+
+/// Only trusts a single certificate, which is how GameStream hosts are verified since they use self-signed certificates.
+#[derive(Debug)]
+struct PinnedCertificate {
+	certificate: CertificateDer<'static>,
+	provider: Arc<CryptoProvider>,
+}
+
+impl ServerCertVerifier for PinnedCertificate {
+	fn verify_server_cert(
+		&self,
+		end_entity: &CertificateDer<'_>,
+		_intermediates: &[CertificateDer<'_>],
+		_server_name: &ServerName<'_>,
+		_ocsp_response: &[u8],
+		_now: UnixTime,
+	) -> Result<ServerCertVerified, rustls::Error> {
+		if end_entity.as_ref() == self.certificate.as_ref() {
+			Ok(ServerCertVerified::assertion())
+		} else {
+			Err(rustls::Error::General("Certificate of the host doesn't match the certificate it paired with.".to_string()))
+		}
+	}
+
+	fn verify_tls12_signature(
+		&self,
+		message: &[u8],
+		cert: &CertificateDer<'_>,
+		dss: &DigitallySignedStruct,
+	) -> Result<HandshakeSignatureValid, rustls::Error> {
+		verify_tls12_signature(message, cert, dss, &self.provider.signature_verification_algorithms)
+	}
+
+	fn verify_tls13_signature(
+		&self,
+		message: &[u8],
+		cert: &CertificateDer<'_>,
+		dss: &DigitallySignedStruct,
+	) -> Result<HandshakeSignatureValid, rustls::Error> {
+		verify_tls13_signature(message, cert, dss, &self.provider.signature_verification_algorithms)
+	}
+
+	fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+		self.provider.signature_verification_algorithms.supported_schemes()
+	}
+}
+
+fn connect(host: &str, port: u16, timeout: Duration) -> Result<TcpStream, String> {
+	let address = (host, port)
+		.to_socket_addrs()
+		.map_err(|e| format!("Failed to resolve '{host}': {e}"))?
+		.next()
+		.ok_or_else(|| format!("Failed to resolve '{host}'."))?;
+
+	let stream = TcpStream::connect_timeout(&address, TIMEOUT)
+		.map_err(|e| format!("Failed to connect to '{host}:{port}': {e}"))?;
+	stream.set_read_timeout(Some(timeout)).map_err(|e| format!("Failed to set timeout: {e}"))?;
+	stream.set_write_timeout(Some(TIMEOUT)).map_err(|e| format!("Failed to set timeout: {e}"))?;
+
+	Ok(stream)
+}
+
+/// Sends a minimal HTTP/1.1 GET request and returns the body of the response.
+fn request(mut stream: impl Read + Write, host: &str, path: &str) -> Result<Vec<u8>, String> {
+	write!(stream, "GET {path} HTTP/1.1\r\nHost: {host}\r\nConnection: close\r\n\r\n")
+		.and_then(|_| stream.flush())
+		.map_err(|e| format!("Failed to send request to '{host}': {e}"))?;
+
+	let mut response = Vec::new();
+	let mut buffer = [0u8; 8192];
+	loop {
+		match stream.read(&mut buffer) {
+			Ok(0) => break,
+			Ok(n) => response.extend_from_slice(&buffer[..n]),
+			// Hosts tend to close the connection without a TLS close_notify, which is fine since we requested that.
+			Err(e) if e.kind() == ErrorKind::UnexpectedEof => break,
+			Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+			Err(e) => return Err(format!("Failed to read response from '{host}': {e}")),
+		}
+	}
+
+	let header_end = response
+		.windows(4)
+		.position(|w| w == b"\r\n\r\n")
+		.ok_or_else(|| format!("Received an incomplete response from '{host}'."))?;
+	let head = String::from_utf8_lossy(&response[..header_end]).to_string();
+	let mut body = response.split_off(header_end + 4);
+
+	let mut lines = head.lines();
+	let status = lines
+		.next()
+		.and_then(|l| l.split_whitespace().nth(1))
+		.and_then(|s| s.parse::<u16>().ok())
+		.ok_or_else(|| format!("Received an invalid response from '{host}'."))?;
+	if status != 200 {
+		return Err(format!("Request to '{host}' failed with HTTP status {status}."));
+	}
+
+	let mut chunked = false;
+	for line in lines {
+		if let Some((name, value)) = line.split_once(':') {
+			let value = value.trim();
+			if name.eq_ignore_ascii_case("transfer-encoding") && value.eq_ignore_ascii_case("chunked") {
+				chunked = true;
+			} else if name.eq_ignore_ascii_case("content-length") {
+				if let Ok(length) = value.parse::<usize>() {
+					body.truncate(length);
+				}
+			}
+		}
+	}
+
+	if chunked {
+		body = decode_chunked(&body).ok_or_else(|| format!("Received an invalid chunked response from '{host}'."))?;
+	}
+
+	Ok(body)
+}
+
+fn decode_chunked(mut data: &[u8]) -> Option<Vec<u8>> {
+	let mut body = Vec::new();
+
+	loop {
+		let line_end = data.windows(2).position(|w| w == b"\r\n")?;
+		let size = std::str::from_utf8(&data[..line_end]).ok()?;
+		let size = usize::from_str_radix(size.split(';').next()?.trim(), 16).ok()?;
+		data = &data[line_end + 2..];
+		if size == 0 {
+			return Some(body);
+		}
+
+		body.extend_from_slice(data.get(..size)?);
+		data = data.get(size + 2..)?;
+	}
+}
+
+/// Parses an XML response and checks the status code that hosts include in it.
+pub fn parse_response(text: &str) -> Result<roxmltree::Document<'_>, String> {
+	let document = roxmltree::Document::parse(text).map_err(|e| format!("Failed to parse response from host: {e}"))?;
+
+	let root = document.root_element();
+	match root.attribute("status_code") {
+		Some("200") | None => Ok(document),
+		Some(code) => Err(format!(
+			"Host responded with status {code}: {}",
+			root.attribute("status_message").unwrap_or("unknown error"),
+		)),
+	}
+}
+
+fn parse_server_info(body: &[u8]) -> Result<ServerInfo, String> {
+	let text = String::from_utf8_lossy(body);
+	let document = parse_response(&text)?;
+	let root = document.root_element();
+
+	Ok(ServerInfo {
+		hostname: child_text(root, "hostname").unwrap_or_default(),
+		uuid: child_text(root, "uniqueid").unwrap_or_default(),
+		app_version: child_text(root, "appversion").unwrap_or_default(),
+		https_port: child_text(root, "HttpsPort").and_then(|p| p.parse().ok()).unwrap_or(DEFAULT_HTTPS_PORT),
+		paired: child_text(root, "PairStatus").as_deref() == Some("1"),
+	})
+}
+
+pub fn child_text(node: roxmltree::Node, name: &str) -> Option<String> {
+	node.children()
+		.find(|n| n.has_tag_name(name))
+		.and_then(|n| n.text())
+		.map(|t| t.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{net::TcpListener, thread};
+
+	use rustls::{server::WebPkiClientVerifier, RootCertStore, ServerConfig, ServerConnection};
+
+	use super::*;
+
+	const BOXART: &[u8] = b"\x89PNG fake boxart";
+
+	struct Identity {
+		certificate: String,
+		key: String,
+	}
+
+	fn generate_identity(name: &str) -> Identity {
+		let key = rcgen::KeyPair::generate().unwrap();
+		let certificate = rcgen::CertificateParams::new(vec![name.to_string()])
+			.unwrap()
+			.self_signed(&key)
+			.unwrap();
+
+		Identity { certificate: certificate.pem(), key: key.serialize_pem() }
+	}
+
+	/// Serves a fixed set of responses over HTTPS, requiring the client to present `client` as certificate.
+	fn mock_host(server: &Identity, client: &Identity, requests: usize) -> u16 {
+		let mut roots = RootCertStore::empty();
+		roots.add(CertificateDer::from_pem_slice(client.certificate.as_bytes()).unwrap()).unwrap();
+		let provider = Arc::new(ring::default_provider());
+		let verifier = WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider.clone()).build().unwrap();
+
+		let config = ServerConfig::builder_with_provider(provider)
+			.with_safe_default_protocol_versions()
+			.unwrap()
+			.with_client_cert_verifier(verifier)
+			.with_single_cert(
+				vec![CertificateDer::from_pem_slice(server.certificate.as_bytes()).unwrap()],
+				PrivateKeyDer::from_pem_slice(server.key.as_bytes()).unwrap(),
+			)
+			.unwrap();
+		let config = Arc::new(config);
+
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let port = listener.local_addr().unwrap().port();
+
+		thread::spawn(move || {
+			for stream in listener.incoming().take(requests) {
+				let connection = ServerConnection::new(config.clone()).unwrap();
+				let mut stream = StreamOwned::new(connection, stream.unwrap());
+
+				let mut request = Vec::new();
+				let mut buffer = [0u8; 1024];
+				while !request.ends_with(b"\r\n\r\n") {
+					match stream.read(&mut buffer) {
+						Ok(0) | Err(_) => break,
+						Ok(n) => request.extend_from_slice(&buffer[..n]),
+					}
+				}
+				let request = String::from_utf8_lossy(&request);
+				let path = request.split_whitespace().nth(1).unwrap_or_default();
+
+				let body: Vec<u8> = if path.starts_with("/serverinfo") {
+					b"<?xml version=\"1.0\" encoding=\"utf-8\"?><root status_code=\"200\"><hostname>mock</hostname>\
+					<uniqueid>1234-5678</uniqueid><HttpsPort>47984</HttpsPort><PairStatus>1</PairStatus></root>"
+						.to_vec()
+				} else if path.starts_with("/applist") {
+					b"<?xml version=\"1.0\" encoding=\"utf-8\"?><root status_code=\"200\">\
+					<App><IsHdrSupported>0</IsHdrSupported><AppTitle>Desktop</AppTitle><ID>881448767</ID></App>\
+					<App><IsHdrSupported>1</IsHdrSupported><AppTitle>Cyberpunk &amp; \"2077\"</AppTitle><ID>42</ID></App>\
+					</root>"
+						.to_vec()
+				} else if path.starts_with("/appasset") {
+					BOXART.to_vec()
+				} else {
+					b"<root status_code=\"404\" status_message=\"Not found\"/>".to_vec()
+				};
+
+				let _ = write!(stream, "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n", body.len());
+				let _ = stream.write_all(&body);
+				let _ = stream.flush();
+			}
+		});
+
+		port
+	}
+
+	fn client_identity(identity: &Identity) -> ClientIdentity {
+		ClientIdentity { certificate: identity.certificate.clone(), key: identity.key.clone() }
+	}
+
+	#[test]
+	fn retrieves_server_info_apps_and_boxart() {
+		let server = generate_identity("mock");
+		let client = generate_identity("client");
+		let port = mock_host(&server, &client, 3);
+
+		let connection = Connection::new("127.0.0.1", port, &client_identity(&client), &server.certificate).unwrap();
+
+		let server_info = connection.server_info().unwrap();
+		assert_eq!(server_info.hostname, "mock");
+		assert_eq!(server_info.uuid, "1234-5678");
+		assert_eq!(server_info.https_port, 47984);
+		assert!(server_info.paired);
+
+		let apps = connection.app_list().unwrap();
+		assert_eq!(apps.len(), 2);
+		assert_eq!(apps[0].title, "Desktop");
+		assert_eq!(apps[0].id, Some(881448767));
+		assert_eq!(apps[1].title, "Cyberpunk & \"2077\"");
+		assert_eq!(apps[1].id, Some(42));
+		assert!(!apps[0].hdr_supported);
+		assert!(apps[1].hdr_supported);
+
+		assert_eq!(connection.app_asset(42).unwrap(), BOXART);
+	}
+
+	#[test]
+	fn applies_moonlight_app_settings() {
+		let app = |title: &str, id| MoonlightApp {
+			title: title.to_string(),
+			id,
+			boxart: None,
+			hdr_supported: false,
+			app_collector: false,
+			hidden: false,
+			direct_launch: false,
+		};
+		let mut apps = [app("Desktop", Some(881448767)), app("Cyberpunk", Some(42)), app("Unknown", None)];
+		let settings = [
+			moonlight_conf::AppSettings { id: 42, hidden: true, direct_launch: false },
+			moonlight_conf::AppSettings { id: 881448767, hidden: false, direct_launch: true },
+		];
+
+		apply_app_settings(&mut apps, &settings);
+
+		assert!(!apps[0].hidden && apps[0].direct_launch);
+		assert!(apps[1].hidden && !apps[1].direct_launch);
+		assert!(!apps[2].hidden && !apps[2].direct_launch);
+	}
+
+	#[test]
+	fn rejects_unknown_server_certificate() {
+		let server = generate_identity("mock");
+		let impostor = generate_identity("impostor");
+		let client = generate_identity("client");
+		let port = mock_host(&server, &client, 1);
+
+		let connection = Connection::new("127.0.0.1", port, &client_identity(&client), &impostor.certificate).unwrap();
+		assert!(connection.server_info().is_err());
+	}
+
+	#[test]
+	fn splits_addresses() {
+		assert_eq!(split_address("192.168.1.10"), ("192.168.1.10".to_string(), None));
+		assert_eq!(split_address("192.168.1.10:48000"), ("192.168.1.10".to_string(), Some(48000)));
+		assert_eq!(split_address("[fe80::1]:48000"), ("fe80::1".to_string(), Some(48000)));
+		assert_eq!(split_address("fe80::1"), ("fe80::1".to_string(), None));
+		assert_eq!(split_address("my-pc"), ("my-pc".to_string(), None));
+	}
+
+	#[test]
+	fn decodes_chunked_bodies() {
+		assert_eq!(decode_chunked(b"4\r\nWiki\r\n5\r\npedia\r\n0\r\n\r\n").unwrap(), b"Wikipedia");
+		assert!(decode_chunked(b"4\r\nWi").is_none());
+	}
+}
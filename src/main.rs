@@ -1,35 +1,333 @@
 use clap::{ArgAction, Parser};
-use dialoguer::{theme::ColorfulTheme, Select};
-use steam_shortcuts_util::{parse_shortcuts, shortcut::ShortcutOwned, shortcuts_to_bytes, Shortcut};
-use std::{io::Cursor, path::PathBuf, process::Command};
+use dialoguer::{theme::ColorfulTheme, Confirm, Select};
+use apps::{AppSource, Backend, MoonlightApp, MoonlightCli};
+use config::{AppPolicy, Config, Filters, HostConfig, Pattern, StreamOption, StreamOptions};
+use gamestream::NativeClient;
+use shortcuts::{AppIdMode, Changes};
+use steam::RunningPolicy;
+use steam_shortcuts_util::{shortcut::ShortcutOwned, Shortcut};
+use output::{
+	info, AppReport, BackupReport, BackupsReport, ChangesReport, ConfigReport, Empty, HostReport, HostStatus, ListReport,
+	OutputFormat, PairReport, RemoveReport, ShortcutReport, StatusReport, SyncReport, UpdateReport,
+};
+use std::{io::IsTerminal, path::{Path, PathBuf}, process::ExitCode};
+use time::UtcTime;
+
+mod apps;
+mod artwork;
+mod backups;
+mod boxart;
+mod collections;
+mod config;
+mod gamestream;
+mod moonlight_conf;
+mod output;
+mod pairing;
+mod placeholder;
+mod quoting;
+mod shortcuts;
+mod state;
+mod steam;
+mod time;
+
+/// Location of the shortcuts file, relative to the directory of a Steam user.
+const SHORTCUTS_PATH: &str = "config/shortcuts.vdf";
 
 #[derive(Parser, Debug)]
 #[clap(version)]
 struct Args {
-	/// Host to retrieve apps from.
-	host: String,
+	#[clap(subcommand)]
+	command: Subcommand,
+
+	/// Path to the configuration file (defaults to `$XDG_CONFIG_HOME/moonlight-steam-shortcuts/config.toml`).
+	#[clap(short, long, global = true)]
+	config: Option<PathBuf>,
 
 	/// Path to the Moonlight executable.
-	#[clap(short, long)]
+	#[clap(short, long, global = true)]
 	moonlight: Option<PathBuf>,
 
 	/// Path to the userdata directory of Steam.
-	#[clap(short, long)]
+	#[clap(short, long, global = true)]
 	steam_userdata: Option<PathBuf>,
 
-	/// Don't remove existing games tagged as "moonlight".
-	#[clap(long = "no-sync", action = ArgAction::SetFalse)]
-	sync: bool,
+	/// What to do when Steam is running, as it reverts changes to the shortcuts when it exits (defaults to refuse).
+	#[clap(long, value_enum, global = true)]
+	steam_running: Option<RunningPolicy>,
 
-	/// Don't override the shortcuts file, just print the Moonlight apps that were found.
+	/// How to retrieve the apps of a host (defaults to native).
+	#[clap(long, value_enum, global = true)]
+	backend: Option<Backend>,
+
+	/// Format of the output, JSON documents are meant for other programs.
+	#[clap(long, value_enum, global = true, default_value_t)]
+	output: OutputFormat,
+}
+
+/// Selection of the hosts to retrieve apps from.
+#[derive(clap::Args, Debug)]
+struct HostArgs {
+	/// Names or addresses of the hosts to retrieve apps from, overrides the hosts in the configuration file.
+	///
+	/// When no hosts are given, one of the hosts paired with Moonlight can be selected.
+	hosts: Vec<String>,
+
+	/// Retrieve apps from all hosts that Moonlight is paired with.
 	#[clap(long)]
-	dry_run: bool,
+	all_paired: bool,
+
+	/// Only create shortcuts for apps matching this pattern, in addition to the filters in the configuration file.
+	///
+	/// Patterns are exact titles, `glob:<pattern>`, `regex:<pattern>` or `is:<property>`, where the property is one of
+	/// `hdr`, `app-collector`, `hidden` and `direct-launch`.
+	#[clap(long = "include", value_name = "PATTERN")]
+	include: Vec<Pattern>,
+
+	/// Don't create shortcuts for apps matching this pattern.
+	#[clap(long = "exclude", value_name = "PATTERN")]
+	exclude: Vec<Pattern>,
+
+	/// How to handle apps that are hidden in Moonlight, overrides the configuration file [default: skip].
+	#[clap(long, value_enum, value_name = "POLICY")]
+	hidden: Option<AppPolicy>,
+
+	/// How to handle apps that were added by an app collector, overrides the configuration file [default: include].
+	#[clap(long, value_enum, value_name = "POLICY")]
+	app_collector: Option<AppPolicy>,
+}
+
+impl HostArgs {
+	fn filters(&self) -> Filters {
+		Filters { include: self.include.clone(), exclude: self.exclude.clone() }
+	}
+
+	/// Returns the policy for an app that is hidden or was added by an app collector, together with a description
+	/// of where it was set. The strictest policy wins when both apply.
+	fn app_policy(&self, config: &Config, app: &MoonlightApp) -> Option<(AppPolicy, String)> {
+		let policies = [
+			(app.hidden, "hidden", self.hidden, config.sync.hidden, AppPolicy::Skip),
+			(app.app_collector, "app-collector", self.app_collector, config.sync.app_collector, AppPolicy::Include),
+		];
+
+		policies
+			.into_iter()
+			.filter(|(applies, ..)| *applies)
+			.map(|(_, name, cli, config, default)| match (cli, config) {
+				(Some(policy), _) => (policy, format!("--{name} {policy} on the command line")),
+				(None, Some(policy)) => (policy, format!("sync.{} = '{policy}' in the configuration file", name.replace('-', "_"))),
+				(None, None) => (default, format!("the default policy for {name} apps")),
+			})
+			.max_by_key(|(policy, _)| *policy)
+	}
+}
+
+#[derive(clap::Args, Debug)]
+struct SyncArgs {
+	#[clap(flatten)]
+	hosts: HostArgs,
+
+	/// Don't remove shortcuts of apps that no longer exist on the synced hosts.
+	#[clap(long = "no-prune", action = ArgAction::SetFalse)]
+	prune: bool,
+
+	/// Option for `moonlight stream`, overriding the configuration file, like `fps=60`, `hdr` or `no-vsync`.
+	#[clap(long = "stream", value_name = "OPTION[=VALUE]")]
+	stream: Vec<StreamOption>,
+
+	/// What happens to the app IDs of existing shortcuts when their executable or name changes, overrides the
+	/// configuration file [default: keep].
+	#[clap(long, value_enum, value_name = "MODE")]
+	app_ids: Option<AppIdMode>,
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum Subcommand {
+	/// Create shortcuts for the apps of hosts, updating and removing the shortcuts created before.
+	Sync {
+		#[clap(flatten)]
+		sync: SyncArgs,
+
+		/// Don't change the shortcuts file, just print what would change.
+		#[clap(long)]
+		dry_run: bool,
+	},
+
+	/// List the apps that hosts provide.
+	List {
+		#[clap(flatten)]
+		hosts: HostArgs,
+	},
+
+	/// Show what a sync would change in the shortcuts file.
+	Diff {
+		#[clap(flatten)]
+		sync: SyncArgs,
+	},
+
+	/// Remove Moonlight shortcuts and their artwork, all of them unless hosts or titles are given.
+	Remove {
+		/// Only remove the shortcuts of these hosts.
+		#[clap(long = "host")]
+		hosts: Vec<String>,
+
+		/// Only remove the shortcuts with these titles.
+		titles: Vec<String>,
+
+		/// Don't change the shortcuts file, just print what would be removed.
+		#[clap(long)]
+		dry_run: bool,
+	},
+
+	/// Show which apps are installed as shortcuts, and when their hosts were last synced.
+	Status,
+
+	/// Manage the configuration file.
+	#[clap(subcommand)]
+	Config(ConfigCommand),
+
+	/// Manage the backups of the shortcuts file.
+	#[clap(subcommand)]
+	Backups(BackupsCommand),
+
+	/// Replace the shortcuts file with a backup.
+	Restore {
+		/// Timestamp of the backup, as shown by `backups list`.
+		timestamp: String,
+	},
+
+	/// Pair with a host, so that its apps can be retrieved without Moonlight.
+	Pair {
+		/// Address of the host, or the name Moonlight knows it by.
+		host: String,
+	},
 }
 
-fn main() -> Result<(), String> {
+impl Subcommand {
+	/// Name of the command, as included in the JSON output.
+	fn name(&self) -> &'static str {
+		match self {
+			Subcommand::Sync { .. } => "sync",
+			Subcommand::List { .. } => "list",
+			Subcommand::Diff { .. } => "diff",
+			Subcommand::Remove { .. } => "remove",
+			Subcommand::Status => "status",
+			Subcommand::Config(ConfigCommand::Check) => "config check",
+			Subcommand::Backups(BackupsCommand::List) => "backups list",
+			Subcommand::Restore { .. } => "restore",
+			Subcommand::Pair { .. } => "pair",
+		}
+	}
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum ConfigCommand {
+	/// Validate the configuration file and report unknown keys.
+	Check,
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum BackupsCommand {
+	/// List the backups of the shortcuts file.
+	List,
+}
+
+fn main() -> ExitCode {
 	let args = Args::parse();
+	output::set_format(args.output);
+
+	let command = args.command.name();
+	match &args.command {
+		Subcommand::Sync { sync: sync_args, dry_run } => {
+			output::finish(command, load_config(&args).and_then(|c| sync(&args, &c, sync_args, *dry_run)))
+		},
+		Subcommand::Diff { sync: sync_args } => {
+			output::finish(command, load_config(&args).and_then(|c| sync(&args, &c, sync_args, true)))
+		},
+		Subcommand::List { hosts } => output::finish(command, load_config(&args).and_then(|c| list(&args, &c, hosts))),
+		Subcommand::Remove { hosts, titles, dry_run } => {
+			output::finish(command, load_config(&args).and_then(|c| remove(&args, &c, hosts, titles, *dry_run)))
+		},
+		Subcommand::Status => output::finish(command, load_config(&args).and_then(|c| status(&args, &c))),
+		Subcommand::Config(ConfigCommand::Check) => output::finish(command, check_config(args.config.as_deref())),
+		Subcommand::Pair { host } => {
+			let paired = pairing::pair(host).map(|h| PairReport { name: h.name, uuid: h.uuid, address: h.address });
+			output::finish(command, paired)
+		},
+		Subcommand::Backups(BackupsCommand::List) => output::finish(command, load_config(&args).and_then(|c| list_backups(&args, &c))),
+		Subcommand::Restore { timestamp } => output::finish(command, load_config(&args).and_then(|c| restore(&args, &c, timestamp))),
+	}
+}
+
+fn load_config(args: &Args) -> Result<Config, String> {
+	let (config, unknown_keys) = config::load(args.config.as_deref())?;
+	for key in unknown_keys {
+		info!("Warning: ignoring {key} of the configuration file.");
+	}
+
+	Ok(config)
+}
+
+fn steam_running(args: &Args, config: &Config) -> RunningPolicy {
+	args.steam_running.or(config.steam.running).unwrap_or_default()
+}
+
+fn list_backups(args: &Args, config: &Config) -> Result<BackupsReport, String> {
+	let backups = backups::list(&user_dir(args, config)?)?;
+	if backups.is_empty() {
+		info!("No backups found.");
+	}
+
+	for backup in &backups {
+		info!("{} ({})", backup.timestamp, backup.path.display());
+	}
+
+	Ok(BackupsReport {
+		backups: backups.into_iter().map(|b| BackupReport { timestamp: b.timestamp, path: b.path }).collect(),
+	})
+}
+
+fn restore(args: &Args, config: &Config, timestamp: &str) -> Result<Empty, String> {
+	let userdata_dir = user_dir(args, config)?;
+	steam::guard(steam_running(args, config), || {
+		backups::restore(&userdata_dir, &userdata_dir.join(SHORTCUTS_PATH), timestamp)
+	})?;
+
+	Ok(Empty {})
+}
+
+fn check_config(path: Option<&Path>) -> Result<ConfigReport, String> {
+	let path = match path {
+		Some(path) => path.to_path_buf(),
+		None => config::default_path().ok_or_else(|| "Failed to determine location of the configuration file.".to_string())?,
+	};
+
+	if !path.exists() {
+		return Err(format!("Configuration file '{}' does not exist.", path.display()));
+	}
+
+	let (config, unknown_keys) = config::load(Some(&path))?;
+	let mut problems: Vec<String> = unknown_keys.iter().map(ToString::to_string).collect();
+	problems.extend(config::validate(&config));
+
+	// Stream options can only be checked when Moonlight is installed.
+	let moonlight_path = config.moonlight.clone().or_else(|| which::which("moonlight").ok()).filter(|p| p.is_file());
+	if let Some(moonlight_path) = moonlight_path {
+		let unsupported = unsupported_stream_options(&moonlight_path, &config.all_stream_options());
+		problems.extend(unsupported.into_iter().map(|(path, flag)| format!("{path}: Moonlight doesn't support '{flag}'")));
+	}
 
-	let moonlight_path = match args.moonlight {
+	for problem in &problems {
+		info!("{}: {problem}", path.display());
+	}
+	if problems.is_empty() {
+		info!("Configuration file '{}' is valid.", path.display());
+	}
+
+	Ok(ConfigReport { path, problems })
+}
+
+fn moonlight_path(args: &Args, config: &Config) -> Result<PathBuf, String> {
+	let moonlight_path = match args.moonlight.as_ref().or(config.moonlight.as_ref()) {
 		Some(path) => path.canonicalize().map_err(|e| format!("Failed to find absolute path of moonlight ('{}'): {e}", path.display()))?,
 		None => {
 			which::which("moonlight")
@@ -41,110 +339,714 @@ fn main() -> Result<(), String> {
 		return Err(format!("Moonlight at '{:?}' does not exist or is not a file.", moonlight_path));
 	}
 
-	println!("Found Moonlight at '{moonlight_path:?}'.");
+	info!("Found Moonlight at '{moonlight_path:?}'.");
+	Ok(moonlight_path)
+}
 
-	let userdata_dir = match args.steam_userdata {
-		Some(path) => {
-			if path.ends_with("userdata") {
-				// Assume we got the `userdata` directory.
-				choose_user_dir(path)?
-			} else {
-				// Assume we got the full user directory.
-				path
+/// Determines the hosts to retrieve apps from.
+fn hosts(host_args: &HostArgs, config: &Config) -> Result<Vec<HostConfig>, String> {
+	// Hosts on the command line replace the hosts in the configuration file, but keep their settings.
+	let mut hosts: Vec<HostConfig> = if host_args.hosts.is_empty() {
+		config.hosts.clone()
+	} else {
+		host_args.hosts
+			.iter()
+			.map(|address| {
+				config.hosts
+					.iter()
+					.find(|h| &h.address == address)
+					.cloned()
+					.unwrap_or_else(|| HostConfig { address: address.clone(), ..Default::default() })
+			})
+			.collect()
+	};
+
+	if host_args.all_paired || config.sync.all_paired {
+		for host in moonlight_conf::find_hosts()? {
+			if host.paired && !hosts.iter().any(|h| host.matches(&h.address)) {
+				hosts.push(HostConfig { address: host.name, ..Default::default() });
 			}
-		},
-		None => {
-			let steam_users_dir = xdg::BaseDirectories::new()
-				.map_err(|_| "Failed to retrieve Steam userdata directory. Please provide a directory using --steam-userdata <steam_dir>.".to_string())?
-				.get_data_home().join("Steam/userdata");
+		}
+	}
+
+	if hosts.is_empty() {
+		let host = choose_host()?;
+		hosts.push(config.hosts.iter().find(|h| h.address == host).cloned().unwrap_or(HostConfig { address: host, ..Default::default() }));
+	}
+
+	Ok(hosts)
+}
+
+/// Checks that the installed version of Moonlight supports all stream options.
+fn validate_stream_options(moonlight_path: &Path, config: &Config, cli_stream: &StreamOptions) -> Result<(), String> {
+	let mut options = config.all_stream_options();
+	options.push(("--stream".to_string(), cli_stream));
+
+	let unsupported: Vec<String> = unsupported_stream_options(moonlight_path, &options)
+		.into_iter()
+		.map(|(path, flag)| format!("'{flag}' ({path})"))
+		.collect();
+	if !unsupported.is_empty() {
+		return Err(format!("Moonlight doesn't support the stream option(s) {}.", unsupported.join(", ")));
+	}
+
+	Ok(())
+}
+
+/// Returns the stream options that Moonlight doesn't support, with the path to where they are set.
+fn unsupported_stream_options(moonlight_path: &Path, options: &[(String, &StreamOptions)]) -> Vec<(String, String)> {
+	if options.iter().all(|(_, o)| o.flags().next().is_none()) {
+		return Vec::new();
+	}
 
-			choose_user_dir(steam_users_dir)?
+	let supported = match apps::stream_flags(moonlight_path) {
+		Ok(supported) => supported,
+		Err(e) => {
+			info!("Warning: unable to check the stream options against Moonlight: {e}");
+			return Vec::new();
 		},
 	};
 
-	let shortcuts_path = userdata_dir.join("config/shortcuts.vdf");
+	options
+		.iter()
+		.flat_map(|(path, o)| o.flags().filter(|f| !supported.contains(f)).map(move |f| (path.clone(), f)))
+		.collect()
+}
 
-	let mut shortcuts = if !shortcuts_path.exists() {
-		println!("Creating shortcuts file at {}.", shortcuts_path.display());
-		Vec::new()
-	} else {
-		let shortcuts_file = std::fs::read(&shortcuts_path)
-			.map_err(|e| format!("Failed to read existing shortcuts file: {e}"))?;
-		parse_shortcuts(&shortcuts_file)
-			.map_err(|e| format!("Failed to parse shortcuts: {e}"))?
+/// Retrieves the apps of a host, making sure it is paired first when talking to it directly.
+fn host_apps(source: &dyn AppSource, backend: Backend, host: &str) -> Result<Vec<MoonlightApp>, String> {
+	if backend == Backend::Native {
+		ensure_paired(host)?;
+	}
+
+	info!("Retrieving apps from '{host}' ...");
+	let apps = source.apps(host)?;
+	info!("Finished retrieving apps from '{host}'.");
+
+	Ok(apps)
+}
+
+fn app_source(backend: Backend, moonlight_path: impl FnOnce() -> Result<PathBuf, String>) -> Result<Box<dyn AppSource>, String> {
+	Ok(match backend {
+		Backend::Native => Box::new(NativeClient),
+		Backend::Cli => Box::new(MoonlightCli { moonlight_path: moonlight_path()? }),
+	})
+}
+
+fn list(args: &Args, config: &Config, host_args: &HostArgs) -> Result<ListReport, String> {
+	let backend = args.backend.or(config.backend).unwrap_or_default();
+	let source = app_source(backend, || moonlight_path(args, config))?;
+
+	let mut reports = Vec::new();
+	for host in hosts(host_args, config)? {
+		let apps = match host_apps(source.as_ref(), backend, &host.address) {
+			Ok(apps) => apps,
+			Err(e) => {
+				info!("Failed to retrieve apps from '{}': {e}", host.address);
+				reports.push(HostReport::failed(&host.address, e));
+				continue;
+			},
+		};
+
+		info!("{}:", host.address);
+		let apps: Vec<AppReport> = apps
+			.iter()
+			.map(|app| AppReport::new(app, excluded_by(config, &host, host_args, app)))
+			.collect();
+		for app in &apps {
+			let id = app.id.map(|id| format!(" (ID: {id})")).unwrap_or_default();
+			let properties = if app.properties.is_empty() { String::new() } else { format!(" [{}]", app.properties.join(", ")) };
+			let excluded = app.excluded_by.as_ref().map(|rule| format!(" [excluded by {rule}]")).unwrap_or_default();
+			info!("  {}{id}{properties}{excluded}", app.title);
+		}
+
+		reports.push(HostReport { host: host.address, apps, changes: None, error: None });
+	}
+
+	Ok(ListReport { hosts: reports })
+}
+
+/// Returns the rule that excludes an app of a host, checking the policies for hidden and app collector apps, and the
+/// global, host and command line filters in turn.
+fn excluded_by(config: &Config, host_config: &HostConfig, host_args: &HostArgs, app: &MoonlightApp) -> Option<String> {
+	if let Some((AppPolicy::Skip, rule)) = host_args.app_policy(config, app) {
+		return Some(rule);
+	}
+	if let Some(rule) = config.filters.excluded_by(app) {
+		return Some(format!("{rule} in the configuration file"));
+	}
+	if let Some(rule) = host_config.filters.excluded_by(app) {
+		return Some(format!("{rule} of host '{}' in the configuration file", host_config.address));
+	}
+	host_args.filters().excluded_by(app).map(|rule| format!("{rule} on the command line"))
+}
+
+fn sync(args: &Args, config: &Config, sync_args: &SyncArgs, dry_run: bool) -> Result<SyncReport, String> {
+	let moonlight_path = moonlight_path(args, config)?;
+	let userdata_dir = user_dir(args, config)?;
+	let shortcuts_path = userdata_dir.join(SHORTCUTS_PATH);
+	let hosts = hosts(&sync_args.hosts, config)?;
+
+	if !shortcuts_path.exists() {
+		info!("Creating shortcuts file at {}.", shortcuts_path.display());
+	}
+	let mut shortcuts = shortcuts::read(&shortcuts_path)?;
+
+	let migrated = shortcuts::migrate_legacy_tags(&mut shortcuts);
+	if migrated > 0 {
+		info!("Added host tags to {migrated} existing Moonlight shortcut(s).");
+	}
+	let original = shortcuts.clone();
+
+	let backend = args.backend.or(config.backend).unwrap_or_default();
+	let source = app_source(backend, || Ok(moonlight_path.clone()))?;
+	let cli_stream: StreamOptions = sync_args.stream.iter().cloned().collect();
+	validate_stream_options(&moonlight_path, config, &cli_stream)?;
+
+	let mut state = state::load(&userdata_dir)?;
+	let mut reports = Vec::new();
+	let mut removed_app_ids = Vec::new();
+	let mut synced_app_ids = Vec::new();
+	let mut added_app_ids = Vec::new();
+	let mut remapped_app_ids = Vec::new();
+	// Tags that were migrated have to be written as well, even if no host has changes.
+	let mut changed = migrated > 0;
+	// App IDs of the shortcuts of the synced apps, by host UUID and app key, to remember once the shortcuts are written.
+	let mut identities: Vec<(String, String, u32)> = Vec::new();
+	// Fields that this tool set on the shortcuts of the synced apps, by host and app key.
+	let mut managed = Vec::new();
+	let mut host_uuids = Vec::new();
+	for host in &hosts {
+		let apps = match host_apps(source.as_ref(), backend, &host.address) {
+			Ok(apps) => apps,
+			Err(e) => {
+				// Existing shortcuts of this host are left untouched.
+				info!("Failed to retrieve apps from '{}': {e}", host.address);
+				reports.push(HostReport::failed(&host.address, e));
+				continue;
+			},
+		};
+
+		// Filters are applied before any shortcuts are built, so excluded apps never end up in the shortcuts file.
+		let mut included_apps = Vec::new();
+		let mut app_reports = Vec::new();
+		for app in apps {
+			let excluded_by = excluded_by(config, host, &sync_args.hosts, &app);
+			match &excluded_by {
+				Some(rule) => info!("{} => excluded by {rule}", app.title),
+				None => {
+					let hide = matches!(sync_args.hosts.app_policy(config, &app), Some((AppPolicy::Hide, _)));
+					included_apps.push((app.clone(), hide));
+				},
+			}
+			app_reports.push(AppReport::new(&app, excluded_by));
+		}
+		let new_shortcuts = host_shortcuts(included_apps, &moonlight_path, config, host, &cli_stream);
+
+		// Shortcuts are tracked by the UUID of the host, which survives renamed apps and changed addresses.
+		let uuid = host_identity(&host.address).map(|(_, uuid)| uuid);
+		let previous_addresses = uuid.as_deref().map(|u| state.previous_addresses(u, &host.address)).unwrap_or_default();
+		let known_app_ids = uuid.as_ref().and_then(|u| state.shortcuts.get(u));
+		// Hosts without a known UUID can only be told apart by their address.
+		let host_key = uuid.clone().unwrap_or_else(|| host.address.clone());
+		let managed_fields = state.managed.get(&host_key);
+		let keys: Vec<Option<String>> = new_shortcuts
+			.iter()
+			.map(|(app_id, s)| app_id.map(|id| state::app_key(id, shortcuts::shortcut_profile(s))))
+			.collect();
+		let desired = new_shortcuts
 			.into_iter()
-			.map(|s| s.to_owned())
-			.collect()
+			.zip(&keys)
+			.map(|((_, shortcut), key)| shortcuts::DesiredShortcut {
+				shortcut,
+				known_app_id: key.as_ref().zip(known_app_ids).and_then(|(key, known)| known.get(key).copied()),
+				managed: key.as_ref().zip(managed_fields).and_then(|(key, m)| m.get(key).cloned()).unwrap_or_default(),
+			})
+			.collect();
+
+		// Shortcuts of other hosts are left alone.
+		let prune = sync_args.prune && config.sync.prune;
+		let app_id_mode = sync_args.app_ids.unwrap_or(config.sync.app_ids);
+		let changes =
+			shortcuts::reconcile(&mut shortcuts, &host.address, &previous_addresses, desired, prune, app_id_mode);
+		if let Some(uuid) = &uuid {
+			for (key, app_id) in keys.iter().zip(&changes.app_ids) {
+				identities.extend(key.clone().map(|key| (uuid.clone(), key, *app_id)));
+			}
+		}
+		let host_managed = keys.into_iter().zip(changes.managed.iter().cloned()).filter_map(|(k, m)| Some((k?, m)));
+		managed.push((host_key, host_managed.collect()));
+		host_uuids.push((host.address.clone(), uuid, previous_addresses.clone()));
+		synced_app_ids.extend(shortcuts.iter().filter(|s| shortcuts::is_host_shortcut(s, &host.address)).map(|s| s.app_id));
+		added_app_ids.extend(&changes.added);
+		removed_app_ids.extend(&changes.removed);
+		remapped_app_ids.extend(&changes.remapped);
+		changed |= !changes.is_empty();
+
+		reports.push(HostReport {
+			host: host.address.clone(),
+			apps: app_reports,
+			changes: Some(changes_report(&host.address, &previous_addresses, &changes, &original, &shortcuts)),
+			error: None,
+		});
+	}
+
+	info!();
+	for report in &reports {
+		if let Some(changes) = &report.changes {
+			print_changes(&report.host, changes);
+		}
+	}
+
+	let synced_hosts: Vec<&str> = reports.iter().filter(|r| r.error.is_none()).map(|r| r.host.as_str()).collect();
+	let synced = !dry_run && !synced_hosts.is_empty();
+	// Rewriting an unchanged file would only leave a needless backup, and fail while Steam is running.
+	let written = synced && changed;
+	if written {
+		steam::guard(steam_running(args, config), || {
+			if let Some(backup) = backups::create(&userdata_dir, &shortcuts_path, config.backups.retention)? {
+				info!("Backed up shortcuts file to {}.", backup.path.display());
+			}
+
+			info!("Shortcuts file: {shortcuts_path:?}");
+			shortcuts::write(&shortcuts_path, &shortcuts).map_err(|e| format!("Failed to write shortcuts to file: {e}"))?;
+
+			// Steam rewrites its collections as well, so they are only updated while it is guarded.
+			match collections::remap_app_ids(&userdata_dir, &remapped_app_ids) {
+				Ok(0) => {},
+				Ok(count) => info!("Updated the app IDs in {count} collection(s)."),
+				Err(e) => info!("Failed to update the app IDs in collections: {e}"),
+			}
+			Ok(())
+		})?;
+	} else if synced {
+		info!("Shortcuts file is up to date.");
+	}
+
+	if synced {
+		let grid_dir = artwork::grid_dir(&userdata_dir);
+		for (old_app_id, new_app_id) in &remapped_app_ids {
+			// A shortcut of another host can still have the old app ID, which keeps its artwork then.
+			let still_used = shortcuts.iter().any(|s| s.app_id == *old_app_id);
+			let old_files = artwork::files_hash(&grid_dir, *old_app_id);
+			let result = if still_used {
+				artwork::copy_artwork(&grid_dir, *old_app_id, *new_app_id)
+			} else {
+				artwork::rename_artwork(&grid_dir, *old_app_id, *new_app_id)
+			};
+			if let Err(e) = result {
+				info!("Failed to move artwork of {old_app_id} to {new_app_id}: {e}");
+				continue;
+			}
+
+			// Artwork that this tool generated is still recognized as such under the new app ID.
+			let generated = state.artwork.get(old_app_id).filter(|g| Some(&g.files) == old_files.as_ref()).cloned();
+			if let (Some(generated), Some(files)) = (generated, artwork::files_hash(&grid_dir, *new_app_id)) {
+				state.artwork.insert(*new_app_id, artwork::GeneratedArtwork { files, ..generated });
+			}
+		}
+		for shortcut in shortcuts.iter().filter(|s| synced_app_ids.contains(&s.app_id)) {
+			let app_id = shortcut.app_id;
+			let generated = state.artwork.get(&app_id);
+			let files = artwork::files_hash(&grid_dir, app_id);
+			// Artwork that the user added, or changed since it was generated, is kept.
+			let own = match (&files, &generated) {
+				(None, _) => true,
+				(Some(files), Some(generated)) => *files == generated.files,
+				(Some(_), None) => added_app_ids.contains(&app_id),
+			};
+			if !own {
+				state.artwork.remove(&app_id);
+				continue;
+			}
+
+			let source = if shortcut.icon.is_empty() {
+				let host = shortcuts::shortcut_host(shortcut).unwrap_or_default().to_string();
+				Ok(artwork::ArtworkSource::Placeholder { title: shortcut.app_name.clone(), host })
+			} else {
+				artwork::ArtworkSource::boxart(Path::new(&shortcut.icon))
+			};
+			// Generated artwork is only written again when what it was generated from changed.
+			let result = match source {
+				Ok(source) if files.is_some() && generated.is_some_and(|g| g.source == source) => continue,
+				Ok(source) => artwork::write(&grid_dir, app_id, source),
+				Err(e) => Err(e),
+			};
+			match result {
+				Ok(generated) => {
+					state.artwork.insert(app_id, generated);
+				},
+				Err(e) => info!("Failed to write artwork of '{}': {e}", shortcut.app_name),
+			}
+		}
+
+		// Artwork of shortcuts that no longer exist would otherwise linger in the grid directory.
+		remove_artwork(&grid_dir, &removed_app_ids, &shortcuts);
+
+		let now = time::unix_now();
+		for (host, uuid, previous_addresses) in host_uuids {
+			// The shortcuts of the previous addresses were taken over by the current one.
+			for address in previous_addresses {
+				state.hosts.remove(&address);
+			}
+			let host_state = state.hosts.entry(host).or_default();
+			host_state.last_sync = Some(now);
+			host_state.uuid = uuid;
+		}
+		for (uuid, key, app_id) in identities {
+			state.shortcuts.entry(uuid).or_default().insert(key, app_id);
+		}
+		// Apps that are no longer synced either lost their shortcut or are no longer managed by this tool.
+		for (host_key, fields) in managed {
+			state.managed.insert(host_key, fields);
+		}
+		state.forget_removed(&shortcuts);
+		state::save(&userdata_dir, &state)?;
+	}
+
+	info!();
+	info!("Summary:");
+	for report in &reports {
+		match (&report.changes, &report.error) {
+			(Some(changes), _) => info!(
+				"  {}: found {} app(s), {} added, {} updated, {} removed, {} unchanged",
+				report.host,
+				report.apps.iter().filter(|a| a.excluded_by.is_none()).count(),
+				changes.added.len(),
+				changes.updated.len(),
+				changes.removed.len(),
+				changes.unchanged,
+			),
+			(None, error) => info!("  {}: failed ({})", report.host, error.as_deref().unwrap_or_default()),
+		}
+	}
+
+	let shortcut_reports = shortcuts
+		.iter()
+		.filter(|s| synced_hosts.iter().any(|h| shortcuts::is_host_shortcut(s, h)))
+		.map(ShortcutReport::from)
+		.collect();
+
+	Ok(SyncReport { shortcuts_file: shortcuts_path, written, hosts: reports, shortcuts: shortcut_reports })
+}
+
+/// Describes the changes that a sync makes to the shortcuts of a host.
+fn changes_report(
+	host: &str,
+	previous_addresses: &[String],
+	changes: &Changes,
+	old: &[ShortcutOwned],
+	new: &[ShortcutOwned],
+) -> ChangesReport {
+	// Shortcuts of different hosts can share an app ID, so only look at the shortcuts of this host.
+	let is_own = |s: &ShortcutOwned| {
+		shortcuts::is_host_shortcut(s, host) || previous_addresses.iter().any(|a| shortcuts::is_host_shortcut(s, a))
+	};
+	let find = |shortcuts: &'_ [ShortcutOwned], app_id: u32| shortcuts.iter().find(|s| s.app_id == app_id && is_own(s)).cloned();
+
+	ChangesReport {
+		added: changes.added.iter().filter_map(|id| find(new, *id)).map(|s| ShortcutReport::from(&s)).collect(),
+		updated: changes.updated
+			.iter()
+			.filter_map(|id| {
+				let old_id = changes.remapped.iter().find(|(_, new_id)| new_id == id).map_or(*id, |(old_id, _)| *old_id);
+				Some((find(old, old_id)?, find(new, *id)?))
+			})
+			.map(|(old, new)| UpdateReport { shortcut: ShortcutReport::from(&new), changes: shortcuts::describe_update(&old, &new) })
+			.collect(),
+		removed: changes.removed.iter().filter_map(|id| find(old, *id)).map(|s| ShortcutReport::from(&s)).collect(),
+		unchanged: changes.unchanged,
+	}
+}
+
+fn print_changes(host: &str, changes: &ChangesReport) {
+	info!("{host}:");
+	for shortcut in &changes.added {
+		let hidden = if shortcut.hidden { " (hidden)" } else { "" };
+		info!("  + {} => '{} {}'{hidden}", shortcut.app_name, shortcut.exe, shortcut.launch_options);
+	}
+	for update in &changes.updated {
+		info!("  ~ {} ({})", update.shortcut.app_name, update.changes.join(", "));
+	}
+	for shortcut in &changes.removed {
+		info!("  - {}", shortcut.app_name);
+	}
+	if changes.is_empty() {
+		info!("  no changes");
+	}
+}
+
+/// Removes the artwork of removed shortcuts, unless another shortcut still uses the same app ID.
+fn remove_artwork(grid_dir: &Path, removed_app_ids: &[u32], shortcuts: &[ShortcutOwned]) {
+	for app_id in removed_app_ids.iter().filter(|id| !shortcuts.iter().any(|s| s.app_id == **id)) {
+		if let Err(e) = artwork::remove_artwork(grid_dir, *app_id) {
+			info!("Failed to remove artwork of removed shortcut {app_id}: {e}");
+		}
+	}
+}
+
+fn remove(args: &Args, config: &Config, hosts: &[String], titles: &[String], dry_run: bool) -> Result<RemoveReport, String> {
+	let userdata_dir = user_dir(args, config)?;
+	let shortcuts_path = userdata_dir.join(SHORTCUTS_PATH);
+	let mut shortcuts = shortcuts::read(&shortcuts_path)?;
+	shortcuts::migrate_legacy_tags(&mut shortcuts);
+
+	let selected = |s: &ShortcutOwned| {
+		shortcuts::is_moonlight_shortcut(s)
+			&& (hosts.is_empty() || hosts.iter().any(|h| shortcuts::is_host_shortcut(s, h)))
+			&& (titles.is_empty() || titles.contains(&s.app_name))
 	};
 
-	if args.sync {
-		// Remove all games that are "moonlight" games.
-		shortcuts.retain(|s| !s.tags.contains(&"moonlight".to_string()));
+	let mut removed = Vec::new();
+	let mut removed_hosts = Vec::new();
+	shortcuts.retain(|s| {
+		if !selected(s) {
+			return true;
+		}
+		info!("- {} ({})", s.app_name, shortcuts::shortcut_host(s).unwrap_or("unknown host"));
+		removed.push(ShortcutReport::from(s));
+		removed_hosts.extend(shortcuts::shortcut_host(s).map(str::to_string));
+		false
+	});
+
+	for title in titles.iter().filter(|t| !removed.iter().any(|r| &r.app_name == *t)) {
+		info!("Warning: no Moonlight shortcut with title '{title}' was found.");
+	}
+
+	if removed.is_empty() {
+		info!("No shortcuts to remove.");
+		return Ok(RemoveReport { written: false, removed });
+	}
+
+	if dry_run {
+		info!("Would remove {} shortcut(s).", removed.len());
+		return Ok(RemoveReport { written: false, removed });
+	}
+
+	let mut state = state::load(&userdata_dir)?;
+	steam::guard(steam_running(args, config), || {
+		if let Some(backup) = backups::create(&userdata_dir, &shortcuts_path, config.backups.retention)? {
+			info!("Backed up shortcuts file to {}.", backup.path.display());
+		}
+		shortcuts::write(&shortcuts_path, &shortcuts).map_err(|e| format!("Failed to write shortcuts to file: {e}"))
+	})?;
+	let removed_app_ids: Vec<u32> = removed.iter().map(|r| r.app_id).collect();
+	remove_artwork(&artwork::grid_dir(&userdata_dir), &removed_app_ids, &shortcuts);
+
+	// Hosts without shortcuts left are forgotten as well, like they were never synced.
+	for host in removed_hosts.iter().filter(|h| !shortcuts.iter().any(|s| shortcuts::is_host_shortcut(s, h))) {
+		if let Some(host_state) = state.hosts.remove(host) {
+			let host_key = host_state.uuid.unwrap_or_else(|| host.clone());
+			state.shortcuts.remove(&host_key);
+			state.managed.remove(&host_key);
+		}
+	}
+	state.forget_removed(&shortcuts);
+	state::save(&userdata_dir, &state)?;
+	info!("Removed {} shortcut(s).", removed.len());
+
+	Ok(RemoveReport { written: true, removed })
+}
+
+fn status(args: &Args, config: &Config) -> Result<StatusReport, String> {
+	let userdata_dir = user_dir(args, config)?;
+	let mut shortcuts = shortcuts::read(&userdata_dir.join(SHORTCUTS_PATH))?;
+	shortcuts::migrate_legacy_tags(&mut shortcuts);
+	let state = state::load(&userdata_dir)?;
+
+	let mut hosts: Vec<&str> = shortcuts.iter().filter_map(shortcuts::shortcut_host).collect();
+	hosts.extend(state.hosts.keys().map(String::as_str));
+	hosts.sort_unstable();
+	hosts.dedup();
+
+	if hosts.is_empty() {
+		info!("No Moonlight shortcuts found in {}.", userdata_dir.display());
+	}
+
+	let mut reports = Vec::new();
+	for host in hosts {
+		let last_sync = state.hosts.get(host).and_then(|h| h.last_sync);
+		match last_sync {
+			Some(time) => info!("{host} (last synced {}):", UtcTime::from_unix(time)),
+			None => info!("{host} (never synced):"),
+		}
+
+		let host_shortcuts: Vec<ShortcutReport> = shortcuts
+			.iter()
+			.filter(|s| shortcuts::is_host_shortcut(s, host))
+			.map(ShortcutReport::from)
+			.collect();
+		if host_shortcuts.is_empty() {
+			info!("  no shortcuts");
+		}
+		for shortcut in &host_shortcuts {
+			info!("  {} (app ID: {})", shortcut.app_name, shortcut.app_id);
+		}
+
+		reports.push(HostStatus { host: host.to_string(), last_sync, shortcuts: host_shortcuts });
+	}
+
+	Ok(StatusReport { hosts: reports })
+}
+
+/// Checks whether a host is paired, offering to pair with it if it isn't.
+fn ensure_paired(host: &str) -> Result<(), String> {
+	if NativeClient.is_paired(host)? {
+		return Ok(());
 	}
 
-	println!("Retrieving apps from Moonlight ...");
-	let moonlight_apps = Command::new(&moonlight_path)
-		.args([
-			"list",
-			&args.host,
-			"--csv"
-		])
-		.output()
-		.map_err(|e| format!("Failed to request apps from moonlight: {e}"))?;
-	println!("Finished retrieving apps from Moonlight.");
+	let not_paired = format!("Not paired with '{host}', pair using `{} pair {host}`.", env!("CARGO_PKG_NAME"));
+	if !std::io::stdin().is_terminal() {
+		return Err(not_paired);
+	}
 
-	if !moonlight_apps.status.success() {
-		println!("Output from Moonlight: {moonlight_apps:?}");
-		return Err("Failed to get apps from Moonlight.".to_string());
+	let pair = Confirm::with_theme(&ColorfulTheme::default())
+		.with_prompt(format!("Not paired with '{host}', pair now?"))
+		.default(true)
+		.interact()
+		.map_err(|e| format!("Failed to ask for pairing: {e}"))?;
+	if !pair {
+		return Err(not_paired);
 	}
 
-	let cursor = Cursor::new(moonlight_apps.stdout);
-	let mut reader = csv::Reader::from_reader(cursor);
+	pairing::pair(host).map(|_| ())
+}
+
+/// Creates a shortcut for each of the apps of a host, together with the ID of the app on the host.
+fn host_shortcuts(
+	apps: Vec<(MoonlightApp, bool)>,
+	moonlight_path: &Path,
+	config: &Config,
+	host_config: &HostConfig,
+	cli_stream: &StreamOptions,
+) -> Vec<(Option<u32>, ShortcutOwned)> {
+	let host = host_config.address.as_str();
+	let template = host_config.title_template.as_ref().or(config.title_template.as_ref());
+	let tags: Vec<&String> = config.tags.iter().chain(&host_config.tags).collect();
+	let uses_host_name = template.into_iter().chain(tags.iter().copied()).any(|t| t.contains("{host_name}"));
+	let host_name = if uses_host_name { host_name(host) } else { String::new() };
+
+	// Without profiles every app gets a single shortcut, otherwise one for each profile.
+	let profiles = config.host_profiles(host_config);
+	let variants = if profiles.is_empty() { vec![None] } else { profiles.into_iter().map(Some).collect() };
 
 	let mut new_shortcuts = Vec::new();
-	for record in reader.records() {
-		match record {
-			Ok(record) => {
-				if record.len() != 7 {
-					return Err(format!("Expected exactly 7 entries in record, but got {}: {:?}", record.len(), record));
+	for (app, hide) in apps {
+		let title = app.title.as_str();
+
+		// Only the name shown in Steam changes, Moonlight needs the exact title of the app.
+		let renamed = config.rename.iter().chain(&host_config.rename).fold(title.to_string(), |name, rule| rule.apply(&name));
+		let placeholders = config::Placeholders { title: &renamed, host, host_name: &host_name, app: &app };
+		let name = template.map(|t| placeholders.render(t)).unwrap_or_else(|| renamed.clone());
+
+		let icon = app.boxart.as_ref().map(|p| p.to_string_lossy().to_string()).unwrap_or_default();
+		for variant in &variants {
+			let profile = variant.map(|(_, profile)| profile);
+			let stream_args = config.stream_options(host_config, &app, profile).merge(cli_stream).to_args();
+			let launch_options = quoting::stream_launch_options(host, title, &stream_args);
+
+			let mut name = name.clone();
+			if let Some((profile_name, profile)) = variant {
+				name.push_str(&profile.suffix(profile_name));
+			}
+
+			let mut shortcut = Shortcut::new(
+				"",
+				&name,
+				&quoting::exe(moonlight_path),
+				"",
+				&icon,
+				"",
+				&launch_options,
+			).to_owned();
+			shortcut.is_hidden = hide;
+			shortcut.tags.push(shortcuts::MOONLIGHT_TAG.to_string());
+			shortcut.tags.push(shortcuts::host_tag(host));
+			if let Some((profile_name, _)) = variant {
+				shortcut.tags.push(shortcuts::profile_tag(profile_name));
+			}
+			// Tags with placeholders for properties the app doesn't have are left out.
+			for tag in tags.iter().map(|t| placeholders.render(t)) {
+				if !tag.trim().is_empty() && !shortcut.tags.contains(&tag) {
+					shortcut.tags.push(tag);
 				}
+			}
 
-				let title = &record[0];
-				let launch_options = format!("stream {} \"{title}\"", args.host);
-
-				let icon = if record[6].contains("no_app_image") { "" } else { record[6].strip_prefix("file://").unwrap() };
-				let mut shortcut = Shortcut::new(
-					"",
-					title,
-					&moonlight_path.to_string_lossy(),
-					"",
-					icon,
-					"",
-					&launch_options,
-				).to_owned();
-				shortcut.tags.push("moonlight".to_string());
-
-				println!("{title} => '{} {launch_options}' (icon: '{icon}')", moonlight_path.display());
-				new_shortcuts.push(shortcut);
-			},
-			Err(e) => {
-				return Err(format!("Failed to parse CSV from Moonlight: {e}"));
-			},
+			new_shortcuts.push((app.id, shortcut));
 		}
 	}
 
-	if !args.dry_run {
-		shortcuts.extend(new_shortcuts);
-		let serialized = shortcuts_to_bytes(&shortcuts.iter().map(ShortcutOwned::borrow).collect());
-		println!("Shortcuts file: {shortcuts_path:?}");
-		std::fs::write(&shortcuts_path, serialized)
-			.map_err(|e| format!("Failed to write shortcuts to file: {e}"))?;
+	new_shortcuts
+}
+
+/// Returns the name a host reports for itself, as known from pairing, or the address if it isn't known.
+fn host_name(address: &str) -> String {
+	host_identity(address).map(|(name, _)| name).unwrap_or_else(|| address.to_string())
+}
+
+/// Returns the name and UUID of a host that was paired with this tool or with Moonlight.
+fn host_identity(address: &str) -> Option<(String, String)> {
+	if let Ok(Some(paired)) = pairing::find_host(address) {
+		return Some((paired.name, paired.uuid));
+	}
+
+	moonlight_conf::find_hosts()
+		.ok()
+		.and_then(|hosts| hosts.into_iter().find(|h| h.matches(address)))
+		.map(|h| (h.name, h.uuid))
+}
+
+/// Lets the user pick one of the hosts that Moonlight is paired with.
+fn choose_host() -> Result<String, String> {
+	let hosts: Vec<_> = moonlight_conf::find_hosts()
+		.map_err(|e| format!("No hosts given and failed to find hosts paired with Moonlight: {e}"))?
+		.into_iter()
+		.filter(|h| h.paired)
+		.collect();
+
+	match hosts.len() {
+		0 => return Err("No hosts given and Moonlight isn't paired with any hosts.".to_string()),
+		1 => {
+			info!("Using host '{}'.", hosts[0].name);
+			return Ok(hosts[0].name.clone());
+		},
+		_ => {},
 	}
 
-	Ok(())
+	let selection = Select::with_theme(&ColorfulTheme::default())
+		.with_prompt("Pick a host (or provide one on the command line):")
+		.default(0)
+		.items(&hosts.iter().map(|h| format!("{} ({})", h.name, h.address().unwrap_or("unknown address"))).collect::<Vec<String>>())
+		.interact()
+		.map_err(|e| format!("Failed to select host: {e}"))?;
+
+	Ok(hosts[selection].name.clone())
+}
+
+/// Determines the directory of the Steam user to manage the shortcuts of.
+fn user_dir(args: &Args, config: &Config) -> Result<PathBuf, String> {
+	let account = config.steam.account.as_deref();
+	match args.steam_userdata.as_ref().or(config.steam.userdata.as_ref()) {
+		Some(path) => {
+			if path.ends_with("userdata") {
+				// Assume we got the `userdata` directory.
+				choose_user_dir(path.clone(), account)
+			} else {
+				// Assume we got the full user directory.
+				Ok(path.clone())
+			}
+		},
+		None => {
+			let steam_users_dir = xdg::BaseDirectories::new()
+				.map_err(|_| "Failed to retrieve Steam userdata directory. Please provide a directory using --steam-userdata <steam_dir>.".to_string())?
+				.get_data_home().join("Steam/userdata");
+
+			choose_user_dir(steam_users_dir, account)
+		},
+	}
 }
 
-fn choose_user_dir(steam_users_dir: PathBuf) -> Result<PathBuf, String> {
+fn choose_user_dir(steam_users_dir: PathBuf, account: Option<&str>) -> Result<PathBuf, String> {
 	let user_dirs: Vec<PathBuf> = std::fs::read_dir(steam_users_dir)
 		.map_err(|e| format!("Failed to read Steam user dir: {e}"))?
 		.filter_map(Result::ok)
@@ -152,12 +1054,22 @@ fn choose_user_dir(steam_users_dir: PathBuf) -> Result<PathBuf, String> {
 		.filter(|d| d.is_dir())
 		.collect();
 
+	let usernames = user_dirs_to_usernames(&user_dirs);
+
+	if let Some(account) = account {
+		// Match on either the account ID (the name of the directory) or the persona name.
+		return user_dirs
+			.iter()
+			.zip(&usernames)
+			.find(|(dir, name)| dir.file_name().is_some_and(|d| d == account) || *name == account)
+			.map(|(dir, _)| dir.clone())
+			.ok_or_else(|| format!("Failed to find a Steam user with account '{account}'."));
+	}
+
 	if user_dirs.len() == 1 {
 		return Ok(user_dirs[0].clone());
 	}
 
-	let usernames = user_dirs_to_usernames(&user_dirs);
-
 	let options = user_dirs.iter().zip(usernames);
 
 	let selection = Select::with_theme(&ColorfulTheme::default())
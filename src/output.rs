@@ -0,0 +1,313 @@
+use std::{path::PathBuf, process::ExitCode, sync::atomic::{AtomicBool, Ordering}};
+
+use serde::Serialize;
+use steam_shortcuts_util::shortcut::ShortcutOwned;
+
+use crate::{apps::{AppProperty, MoonlightApp}, shortcuts};
+
+/// Version of the structure of the JSON documents.
+///
+/// With `--output json` every command writes a single document to stdout:
+/// `{ "schema_version": 1, "command": "sync", "result": { ... }, "error": null }`, where `result` is the report of
+/// the command (`null` if it failed before producing one). Fields are only added within a version, removing or
+/// changing a field bumps it.
+pub const SCHEMA_VERSION: u32 = 1;
+
+static JSON: AtomicBool = AtomicBool::new(false);
+
+/// Format in which the results of a command are written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+	/// Text for humans.
+	#[default]
+	Text,
+
+	/// A JSON document on stdout, with messages for humans on stderr.
+	Json,
+}
+
+pub fn set_format(format: OutputFormat) {
+	JSON.store(format == OutputFormat::Json, Ordering::Relaxed);
+}
+
+pub fn is_json() -> bool {
+	JSON.load(Ordering::Relaxed)
+}
+
+/// Prints a message for humans, which goes to stderr when a JSON document is written to stdout.
+macro_rules! info {
+	($($arg:tt)*) => {
+		if $crate::output::is_json() {
+			eprintln!($($arg)*);
+		} else {
+			println!($($arg)*);
+		}
+	};
+}
+pub(crate) use info;
+
+/// Result of a command, as included in the JSON document.
+pub trait Report: Serialize {
+	/// Error to report alongside the result, for commands that only partially succeeded.
+	fn error(&self) -> Option<String> {
+		None
+	}
+}
+
+#[derive(Serialize)]
+struct Document<'a, R> {
+	schema_version: u32,
+	command: &'a str,
+	result: Option<&'a R>,
+	error: Option<String>,
+}
+
+/// Writes the outcome of a command, returning the exit code of the process.
+pub fn finish<R: Report>(command: &str, result: Result<R, String>) -> ExitCode {
+	let (report, error) = match &result {
+		Ok(report) => (Some(report), report.error()),
+		Err(e) => (None, Some(e.clone())),
+	};
+
+	if is_json() {
+		let document = Document { schema_version: SCHEMA_VERSION, command, result: report, error: error.clone() };
+		match serde_json::to_string_pretty(&document) {
+			Ok(json) => println!("{json}"),
+			Err(e) => eprintln!("Failed to serialize result: {e}"),
+		}
+	} else if let Some(error) = &error {
+		eprintln!("Error: {error}");
+	}
+
+	if error.is_some() { ExitCode::FAILURE } else { ExitCode::SUCCESS }
+}
+
+/// Report of a command that has nothing to report besides succeeding.
+#[derive(Serialize)]
+pub struct Empty {}
+
+impl Report for Empty {}
+
+/// App that a host provides.
+#[derive(Debug, Serialize)]
+pub struct AppReport {
+	pub title: String,
+
+	/// ID of the app on the host, if known.
+	pub id: Option<u32>,
+
+	/// Local path to the boxart of the app.
+	pub boxart: Option<PathBuf>,
+
+	/// Properties of the app, as used in `is:<property>` patterns.
+	pub properties: Vec<&'static str>,
+
+	/// Whether the filters allow creating a shortcut for the app.
+	pub included: bool,
+
+	/// Description of the filter rule that excludes the app.
+	pub excluded_by: Option<String>,
+}
+
+impl AppReport {
+	pub fn new(app: &MoonlightApp, excluded_by: Option<String>) -> Self {
+		Self {
+			title: app.title.clone(),
+			id: app.id,
+			boxart: app.boxart.clone(),
+			properties: AppProperty::ALL.into_iter().filter(|p| p.of(app)).map(AppProperty::name).collect(),
+			included: excluded_by.is_none(),
+			excluded_by,
+		}
+	}
+}
+
+/// Shortcut in the shortcuts file of Steam.
+#[derive(Debug, Clone, Serialize)]
+pub struct ShortcutReport {
+	/// ID that Steam uses for the shortcut, also used for the names of its artwork.
+	pub app_id: u32,
+	pub app_name: String,
+	pub exe: String,
+	pub launch_options: String,
+	pub icon: String,
+	pub tags: Vec<String>,
+
+	/// Whether the shortcut is hidden in the Steam library.
+	pub hidden: bool,
+
+	/// Host the shortcut was created for.
+	pub host: Option<String>,
+
+	/// Stream profile the shortcut was created for.
+	pub profile: Option<String>,
+}
+
+impl From<&ShortcutOwned> for ShortcutReport {
+	fn from(shortcut: &ShortcutOwned) -> Self {
+		Self {
+			app_id: shortcut.app_id,
+			app_name: shortcut.app_name.clone(),
+			exe: shortcut.exe.clone(),
+			launch_options: shortcut.launch_options.clone(),
+			icon: shortcut.icon.clone(),
+			tags: shortcut.tags.clone(),
+			hidden: shortcut.is_hidden,
+			host: shortcuts::shortcut_host(shortcut).map(String::from),
+			profile: shortcuts::shortcut_profile(shortcut).map(String::from),
+		}
+	}
+}
+
+/// Changes that a sync makes to the shortcuts of a host.
+#[derive(Debug, Default, Serialize)]
+pub struct ChangesReport {
+	pub added: Vec<ShortcutReport>,
+	pub updated: Vec<UpdateReport>,
+	pub removed: Vec<ShortcutReport>,
+
+	/// Number of shortcuts that were already up to date.
+	pub unchanged: usize,
+}
+
+impl ChangesReport {
+	pub fn is_empty(&self) -> bool {
+		self.added.is_empty() && self.updated.is_empty() && self.removed.is_empty()
+	}
+}
+
+/// Shortcut that is updated by a sync.
+#[derive(Debug, Serialize)]
+pub struct UpdateReport {
+	/// The shortcut after the update.
+	pub shortcut: ShortcutReport,
+
+	/// Descriptions of the fields that changed.
+	pub changes: Vec<String>,
+}
+
+/// Outcome of retrieving the apps of a host.
+#[derive(Debug, Serialize)]
+pub struct HostReport {
+	pub host: String,
+
+	/// Apps that the host provides, empty if they couldn't be retrieved.
+	pub apps: Vec<AppReport>,
+
+	/// Changes to the shortcuts of the host, for commands that sync.
+	pub changes: Option<ChangesReport>,
+
+	/// Why the apps of the host couldn't be retrieved.
+	pub error: Option<String>,
+}
+
+impl HostReport {
+	pub fn failed(host: &str, error: String) -> Self {
+		Self { host: host.to_string(), apps: Vec::new(), changes: None, error: Some(error) }
+	}
+}
+
+/// Report of the `list` command.
+#[derive(Debug, Serialize)]
+pub struct ListReport {
+	pub hosts: Vec<HostReport>,
+}
+
+impl Report for ListReport {
+	fn error(&self) -> Option<String> {
+		hosts_error(&self.hosts)
+	}
+}
+
+/// Report of the `sync` and `diff` commands.
+#[derive(Debug, Serialize)]
+pub struct SyncReport {
+	pub shortcuts_file: PathBuf,
+
+	/// Whether the shortcuts file was written.
+	pub written: bool,
+
+	pub hosts: Vec<HostReport>,
+
+	/// Shortcuts of the synced hosts, as they are (or would be) in the shortcuts file.
+	pub shortcuts: Vec<ShortcutReport>,
+}
+
+impl Report for SyncReport {
+	fn error(&self) -> Option<String> {
+		hosts_error(&self.hosts)
+	}
+}
+
+fn hosts_error(hosts: &[HostReport]) -> Option<String> {
+	let failed = hosts.iter().filter(|h| h.error.is_some()).count();
+	(failed > 0).then(|| format!("Failed to retrieve apps from {failed} of {} host(s).", hosts.len()))
+}
+
+/// Report of the `remove` command.
+#[derive(Debug, Serialize)]
+pub struct RemoveReport {
+	/// Whether the shortcuts file was written.
+	pub written: bool,
+
+	pub removed: Vec<ShortcutReport>,
+}
+
+impl Report for RemoveReport {}
+
+/// Report of the `status` command.
+#[derive(Debug, Serialize)]
+pub struct StatusReport {
+	pub hosts: Vec<HostStatus>,
+}
+
+impl Report for StatusReport {}
+
+#[derive(Debug, Serialize)]
+pub struct HostStatus {
+	pub host: String,
+
+	/// Time of the last successful sync, in seconds since the Unix epoch.
+	pub last_sync: Option<u64>,
+
+	pub shortcuts: Vec<ShortcutReport>,
+}
+
+/// Report of the `config check` command.
+#[derive(Debug, Serialize)]
+pub struct ConfigReport {
+	pub path: PathBuf,
+	pub problems: Vec<String>,
+}
+
+impl Report for ConfigReport {
+	fn error(&self) -> Option<String> {
+		(!self.problems.is_empty()).then(|| format!("Found {} problem(s) in configuration file.", self.problems.len()))
+	}
+}
+
+/// Report of the `backups list` command.
+#[derive(Debug, Serialize)]
+pub struct BackupsReport {
+	pub backups: Vec<BackupReport>,
+}
+
+impl Report for BackupsReport {}
+
+#[derive(Debug, Serialize)]
+pub struct BackupReport {
+	/// Time (UTC) at which the backup was made, formatted as `YYYYMMDD-HHMMSS`, followed by a counter (`-2`, `-3`, ...)
+	/// for further backups made within the same second.
+	pub timestamp: String,
+	pub path: PathBuf,
+}
+
+/// Report of the `pair` command.
+#[derive(Debug, Serialize)]
+pub struct PairReport {
+	pub name: String,
+	pub uuid: String,
+	pub address: String,
+}
+
+impl Report for PairReport {}
@@ -0,0 +1,543 @@
+use std::{path::PathBuf, time::Duration};
+
+use aes::{cipher::{generic_array::GenericArray, BlockDecrypt, BlockEncrypt, KeyInit}, Aes128};
+use rand_core::{OsRng, RngCore};
+use rsa::{
+	pkcs1v15::{Signature, SigningKey, VerifyingKey},
+	pkcs8::{DecodePrivateKey, DecodePublicKey, EncodePrivateKey, LineEnding},
+	signature::{SignatureEncoding, Signer, Verifier},
+	RsaPrivateKey,
+	RsaPublicKey,
+};
+use rustls::pki_types::{pem::PemObject, CertificateDer};
+use serde::{Deserialize, Serialize};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+use crate::{
+	config::APP_NAME,
+	gamestream::{self, ClientIdentity, Connection, DEFAULT_HTTP_PORT, UNIQUE_ID},
+	moonlight_conf,
+	output::info,
+};
+
+/// Name under which this client shows up on the host.
+const DEVICE_NAME: &str = "moonlight-steam-shortcuts";
+
+/// How long to wait for the user to enter the PIN on the host.
+const PIN_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// Host that was paired using this tool.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PairedHost {
+	/// Name of the host.
+	pub name: String,
+
+	/// Unique identifier of the host.
+	pub uuid: String,
+
+	/// Address that was used to pair with the host.
+	pub address: String,
+
+	/// Certificate (PEM) of the host.
+	pub certificate: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct PairedHosts {
+	#[serde(default)]
+	hosts: Vec<PairedHost>,
+}
+
+fn state_dir() -> Result<PathBuf, String> {
+	xdg::BaseDirectories::with_prefix(APP_NAME)
+		.map_err(|e| format!("Failed to determine state directory: {e}"))?
+		.create_state_directory("")
+		.map_err(|e| format!("Failed to create state directory: {e}"))
+}
+
+/// Loads the identity that this tool uses for the hosts it paired with, if it was created before.
+pub fn load_identity() -> Result<Option<ClientIdentity>, String> {
+	let dir = state_dir()?;
+	let (certificate_path, key_path) = (dir.join("client.pem"), dir.join("client.key"));
+	if !certificate_path.exists() || !key_path.exists() {
+		return Ok(None);
+	}
+
+	let certificate = std::fs::read_to_string(&certificate_path)
+		.map_err(|e| format!("Failed to read client certificate '{}': {e}", certificate_path.display()))?;
+	let key = std::fs::read_to_string(&key_path)
+		.map_err(|e| format!("Failed to read client key '{}': {e}", key_path.display()))?;
+
+	Ok(Some(ClientIdentity { certificate, key }))
+}
+
+fn load_or_create_identity() -> Result<ClientIdentity, String> {
+	if let Some(identity) = load_identity()? {
+		return Ok(identity);
+	}
+
+	info!("Generating client certificate ...");
+	let identity = generate_identity()?;
+
+	let dir = state_dir()?;
+	write_private(&dir.join("client.key"), &identity.key)?;
+	std::fs::write(dir.join("client.pem"), &identity.certificate)
+		.map_err(|e| format!("Failed to write client certificate: {e}"))?;
+
+	Ok(identity)
+}
+
+/// Generates an RSA key with a self-signed certificate, as hosts only accept RSA certificates for pairing.
+fn generate_identity() -> Result<ClientIdentity, String> {
+	let key = RsaPrivateKey::new(&mut OsRng, 2048).map_err(|e| format!("Failed to generate client key: {e}"))?;
+	let key = key.to_pkcs8_pem(LineEnding::LF).map_err(|e| format!("Failed to encode client key: {e}"))?.to_string();
+
+	let key_pair = rcgen::KeyPair::from_pkcs8_pem_and_sign_algo(&key, &rcgen::PKCS_RSA_SHA256)
+		.map_err(|e| format!("Failed to load client key: {e}"))?;
+	let mut params = rcgen::CertificateParams::default();
+	params.distinguished_name.push(rcgen::DnType::CommonName, "NVIDIA GameStream Client");
+	let certificate = params
+		.self_signed(&key_pair)
+		.map_err(|e| format!("Failed to generate client certificate: {e}"))?
+		.pem();
+
+	Ok(ClientIdentity { certificate, key })
+}
+
+/// Writes a file that only the current user can read.
+fn write_private(path: &std::path::Path, contents: &str) -> Result<(), String> {
+	use std::{io::Write, os::unix::fs::OpenOptionsExt};
+
+	std::fs::OpenOptions::new()
+		.write(true)
+		.create(true)
+		.truncate(true)
+		.mode(0o600)
+		.open(path)
+		.and_then(|mut f| f.write_all(contents.as_bytes()))
+		.map_err(|e| format!("Failed to write '{}': {e}", path.display()))
+}
+
+fn read_paired_hosts() -> Result<PairedHosts, String> {
+	let path = state_dir()?.join("paired_hosts.toml");
+	if !path.exists() {
+		return Ok(PairedHosts::default());
+	}
+
+	let contents = std::fs::read_to_string(&path).map_err(|e| format!("Failed to read '{}': {e}", path.display()))?;
+	toml::from_str(&contents).map_err(|e| format!("Failed to parse '{}': {e}", path.display()))
+}
+
+fn save_paired_host(host: PairedHost) -> Result<(), String> {
+	let mut paired_hosts = read_paired_hosts()?;
+	paired_hosts.hosts.retain(|h| h.uuid != host.uuid);
+	paired_hosts.hosts.push(host);
+
+	let path = state_dir()?.join("paired_hosts.toml");
+	let contents = toml::to_string(&paired_hosts).map_err(|e| format!("Failed to serialize paired hosts: {e}"))?;
+	std::fs::write(&path, contents).map_err(|e| format!("Failed to write '{}': {e}", path.display()))
+}
+
+/// Finds a host that was paired using this tool, by name, UUID or address.
+pub fn find_host(host: &str) -> Result<Option<PairedHost>, String> {
+	Ok(read_paired_hosts()?
+		.hosts
+		.into_iter()
+		.find(|h| h.name.eq_ignore_ascii_case(host) || h.uuid.eq_ignore_ascii_case(host) || h.address == host))
+}
+
+/// Pairs with a host, which can be given by address or by the name Moonlight knows it by.
+pub fn pair(host: &str) -> Result<PairedHost, String> {
+	let address = match find_host(host)? {
+		Some(paired) => paired.address,
+		None => moonlight_conf::find_hosts()
+			.ok()
+			.and_then(|hosts| hosts.into_iter().find(|h| h.matches(host)))
+			.and_then(|h| h.address().map(String::from))
+			.unwrap_or_else(|| host.to_string()),
+	};
+	let (hostname, port) = gamestream::split_address(&address);
+	let port = port.unwrap_or(DEFAULT_HTTP_PORT);
+
+	let server_info = gamestream::http_server_info(&hostname, port)?;
+	let identity = load_or_create_identity()?;
+	let pairing = Pairing { host: &hostname, port, identity: &identity };
+
+	let pin = format!("{:04}", OsRng.next_u32() % 10000);
+	info!("Enter PIN {pin} on '{}' to pair (for Sunshine this is done in its web interface).", server_info.hostname);
+
+	let server_certificate = match pairing.handshake(&pin, &server_info.app_version) {
+		Ok(certificate) => certificate,
+		Err(e) => {
+			pairing.unpair();
+			return Err(e);
+		},
+	};
+
+	// Finally, the host expects the client to prove it can connect over HTTPS with its certificate.
+	let connection = Connection::new(&hostname, server_info.https_port, &identity, &server_certificate)?;
+	let response = connection.get(&format!("/pair?{}&phrase=pairchallenge", pairing.query()))?;
+	if !is_paired(&response)? {
+		pairing.unpair();
+		return Err("Host rejected the pairing challenge.".to_string());
+	}
+
+	let paired_host = PairedHost {
+		name: server_info.hostname,
+		uuid: server_info.uuid,
+		address,
+		certificate: server_certificate,
+	};
+	save_paired_host(paired_host.clone())?;
+	info!("Paired with '{}'.", paired_host.name);
+
+	Ok(paired_host)
+}
+
+/// Hash algorithm used during pairing, which depends on the version of the host.
+#[derive(Clone, Copy)]
+enum HashAlgorithm {
+	Sha1,
+	Sha256,
+}
+
+impl HashAlgorithm {
+	fn for_version(app_version: &str) -> Self {
+		match app_version.split('.').next().and_then(|v| v.parse::<u32>().ok()) {
+			Some(major) if major >= 7 => HashAlgorithm::Sha256,
+			_ => HashAlgorithm::Sha1,
+		}
+	}
+
+	fn digest(self, parts: &[&[u8]]) -> Vec<u8> {
+		match self {
+			HashAlgorithm::Sha1 => parts.iter().fold(Sha1::new(), |h, p| h.chain_update(p)).finalize().to_vec(),
+			HashAlgorithm::Sha256 => parts.iter().fold(Sha256::new(), |h, p| h.chain_update(p)).finalize().to_vec(),
+		}
+	}
+
+	fn len(self) -> usize {
+		match self {
+			HashAlgorithm::Sha1 => 20,
+			HashAlgorithm::Sha256 => 32,
+		}
+	}
+}
+
+/// State of a pairing attempt with a host.
+struct Pairing<'a> {
+	host: &'a str,
+	port: u16,
+	identity: &'a ClientIdentity,
+}
+
+impl Pairing<'_> {
+	fn query(&self) -> String {
+		format!("uniqueid={UNIQUE_ID}&devicename={DEVICE_NAME}&updateState=1")
+	}
+
+	fn request(&self, params: &str, timeout: Duration) -> Result<String, String> {
+		let body = gamestream::http_get(self.host, self.port, &format!("/pair?{}&{params}", self.query()), timeout)?;
+		let body = String::from_utf8_lossy(&body).to_string();
+		if !is_paired(body.as_bytes())? {
+			return Err("Pairing was rejected by the host.".to_string());
+		}
+
+		Ok(body)
+	}
+
+	fn unpair(&self) {
+		let _ = gamestream::http_get(self.host, self.port, &format!("/unpair?uniqueid={UNIQUE_ID}"), gamestream::TIMEOUT);
+	}
+
+	/// Performs the PIN based key exchange, returning the certificate (PEM) of the host.
+	fn handshake(&self, pin: &str, app_version: &str) -> Result<String, String> {
+		let hash = HashAlgorithm::for_version(app_version);
+		let client_certificate = CertificateDer::from_pem_slice(self.identity.certificate.as_bytes())
+			.map_err(|e| format!("Failed to parse client certificate: {e}"))?;
+		let client_key = RsaPrivateKey::from_pkcs8_pem(&self.identity.key)
+			.map_err(|e| format!("Failed to parse client key: {e}"))?;
+
+		// Both sides derive the AES key from a salt and the PIN that the user entered on the host.
+		let salt = random_bytes::<16>();
+		let aes_key = hash.digest(&[&salt, pin.as_bytes()]);
+		let cipher = Aes128::new(GenericArray::from_slice(&aes_key[..16]));
+
+		// This request only returns once the user entered the PIN on the host.
+		let response = self.request(
+			&format!("phrase=getservercert&salt={}&clientcert={}", to_hex(&salt), to_hex(self.identity.certificate.as_bytes())),
+			PIN_TIMEOUT,
+		)?;
+		let server_certificate = response_hex(&response, "plaincert")?;
+		if server_certificate.is_empty() {
+			return Err("Host is already pairing with another client.".to_string());
+		}
+		let server_certificate = String::from_utf8(server_certificate)
+			.map_err(|_| "Host sent an invalid certificate.".to_string())?;
+		let server_certificate_der = CertificateDer::from_pem_slice(server_certificate.as_bytes())
+			.map_err(|e| format!("Failed to parse certificate of host: {e}"))?;
+		let (server_signature, server_public_key) = certificate_details(&server_certificate_der)?;
+		let (client_signature, _) = certificate_details(&client_certificate)?;
+
+		// Send a challenge that the host can only answer if it knows the PIN.
+		let client_challenge = random_bytes::<16>();
+		let response = self.request(&format!("clientchallenge={}", to_hex(&encrypt(&cipher, &client_challenge))), gamestream::TIMEOUT)?;
+		let challenge_response = decrypt(&cipher, &response_hex(&response, "challengeresponse")?);
+		if challenge_response.len() < hash.len() + 16 {
+			return Err("Host sent an invalid challenge response.".to_string());
+		}
+		let server_response = &challenge_response[..hash.len()];
+		let server_challenge = &challenge_response[hash.len()..hash.len() + 16];
+
+		// Answer the challenge of the host.
+		let client_secret = random_bytes::<16>();
+		let challenge_response_hash = hash.digest(&[server_challenge, &client_signature, &client_secret]);
+		let response = self.request(
+			&format!("serverchallengeresp={}", to_hex(&encrypt(&cipher, &challenge_response_hash))),
+			gamestream::TIMEOUT,
+		)?;
+		let pairing_secret = response_hex(&response, "pairingsecret")?;
+		if pairing_secret.len() <= 16 {
+			return Err("Host sent an invalid pairing secret.".to_string());
+		}
+		let (server_secret, server_secret_signature) = pairing_secret.split_at(16);
+
+		// Verify that we're talking to the owner of the certificate, and that it knew the PIN.
+		let verifying_key = VerifyingKey::<Sha256>::new(server_public_key);
+		let signature = Signature::try_from(server_secret_signature).map_err(|e| format!("Host sent an invalid signature: {e}"))?;
+		verifying_key
+			.verify(server_secret, &signature)
+			.map_err(|_| "Signature of the host is invalid, the connection may have been tampered with.".to_string())?;
+		if hash.digest(&[&client_challenge, &server_signature, server_secret]) != server_response {
+			return Err("The PIN was entered incorrectly.".to_string());
+		}
+
+		// Prove to the host that we own our certificate.
+		let signing_key = SigningKey::<Sha256>::new(client_key);
+		let mut client_pairing_secret = client_secret.to_vec();
+		client_pairing_secret.extend(signing_key.sign(&client_secret).to_vec());
+		self.request(&format!("clientpairingsecret={}", to_hex(&client_pairing_secret)), gamestream::TIMEOUT)?;
+
+		Ok(server_certificate)
+	}
+}
+
+fn is_paired(body: &[u8]) -> Result<bool, String> {
+	let text = String::from_utf8_lossy(body);
+	let document = gamestream::parse_response(&text)?;
+	Ok(gamestream::child_text(document.root_element(), "paired").as_deref() == Some("1"))
+}
+
+fn response_hex(body: &str, name: &str) -> Result<Vec<u8>, String> {
+	let document = gamestream::parse_response(body)?;
+	let value = gamestream::child_text(document.root_element(), name).unwrap_or_default();
+	from_hex(&value).ok_or_else(|| format!("Host sent an invalid value for '{name}'."))
+}
+
+/// Returns the signature and public key of a certificate.
+fn certificate_details(certificate: &CertificateDer) -> Result<(Vec<u8>, RsaPublicKey), String> {
+	let (_, certificate) = x509_parser::parse_x509_certificate(certificate)
+		.map_err(|e| format!("Failed to parse certificate: {e}"))?;
+	let public_key = RsaPublicKey::from_public_key_der(certificate.public_key().raw)
+		.map_err(|e| format!("Certificate doesn't contain an RSA key: {e}"))?;
+
+	Ok((certificate.signature_value.data.to_vec(), public_key))
+}
+
+fn random_bytes<const N: usize>() -> [u8; N] {
+	let mut bytes = [0u8; N];
+	OsRng.fill_bytes(&mut bytes);
+	bytes
+}
+
+/// Encrypts with AES in ECB mode, padding the data with zeroes to a multiple of the block size.
+fn encrypt(cipher: &Aes128, data: &[u8]) -> Vec<u8> {
+	let mut data = data.to_vec();
+	data.resize(data.len().div_ceil(16) * 16, 0);
+	for block in data.chunks_exact_mut(16) {
+		cipher.encrypt_block(GenericArray::from_mut_slice(block));
+	}
+	data
+}
+
+fn decrypt(cipher: &Aes128, data: &[u8]) -> Vec<u8> {
+	let mut data = data.to_vec();
+	for block in data.chunks_exact_mut(16) {
+		cipher.decrypt_block(GenericArray::from_mut_slice(block));
+	}
+	data
+}
+
+fn to_hex(data: &[u8]) -> String {
+	data.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+	if !hex.len().is_multiple_of(2) || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+		return None;
+	}
+
+	(0..hex.len()).step_by(2).map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{
+		io::{Read, Write},
+		net::{TcpListener, TcpStream},
+		sync::OnceLock,
+		thread,
+	};
+
+	use super::*;
+
+	const PIN: &str = "4711";
+
+	/// Identities of the client and the host, which are shared by the tests as generating RSA keys is slow.
+	fn identities() -> &'static (ClientIdentity, ClientIdentity) {
+		static IDENTITIES: OnceLock<(ClientIdentity, ClientIdentity)> = OnceLock::new();
+		IDENTITIES.get_or_init(|| (generate_identity().unwrap(), generate_identity().unwrap()))
+	}
+
+	fn read_path(stream: &mut TcpStream) -> String {
+		let mut request = Vec::new();
+		let mut buffer = [0u8; 1024];
+		while !request.ends_with(b"\r\n\r\n") {
+			match stream.read(&mut buffer) {
+				Ok(0) | Err(_) => break,
+				Ok(n) => request.extend_from_slice(&buffer[..n]),
+			}
+		}
+		String::from_utf8_lossy(&request).split_whitespace().nth(1).unwrap_or_default().to_string()
+	}
+
+	/// Answers the pairing requests like a host of the given version on which the user entered `pin`.
+	fn mock_host(server: ClientIdentity, pin: &'static str, app_version: &str) -> u16 {
+		let hash = HashAlgorithm::for_version(app_version);
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let port = listener.local_addr().unwrap().port();
+
+		thread::spawn(move || {
+			let server_key = SigningKey::<Sha256>::new(RsaPrivateKey::from_pkcs8_pem(&server.key).unwrap());
+			let server_certificate = CertificateDer::from_pem_slice(server.certificate.as_bytes()).unwrap();
+			let (server_signature, _) = certificate_details(&server_certificate).unwrap();
+			let server_secret = random_bytes::<16>();
+			let server_challenge = random_bytes::<16>();
+			let mut cipher = None;
+			let mut client_certificate = Vec::new();
+			let mut challenge_response_hash = Vec::new();
+
+			for stream in listener.incoming().take(4) {
+				let mut stream = stream.unwrap();
+				let path = read_path(&mut stream);
+				let param = |name: &str| {
+					let prefix = format!("{name}=");
+					path.split(['?', '&']).find_map(|p| p.strip_prefix(prefix.as_str())).and_then(from_hex)
+				};
+
+				let mut paired = true;
+				let value = if let Some(salt) = param("salt") {
+					let aes_key = hash.digest(&[&salt, pin.as_bytes()]);
+					cipher = Some(Aes128::new(GenericArray::from_slice(&aes_key[..16])));
+					client_certificate = param("clientcert").unwrap();
+					format!("<plaincert>{}</plaincert>", to_hex(server.certificate.as_bytes()))
+				} else if let Some(challenge) = param("clientchallenge") {
+					let cipher = cipher.as_ref().unwrap();
+					let client_challenge = decrypt(cipher, &challenge);
+					let mut response = hash.digest(&[&client_challenge, &server_signature, &server_secret]);
+					response.extend(server_challenge);
+					format!("<challengeresponse>{}</challengeresponse>", to_hex(&encrypt(cipher, &response)))
+				} else if let Some(response) = param("serverchallengeresp") {
+					challenge_response_hash = decrypt(cipher.as_ref().unwrap(), &response)[..hash.len()].to_vec();
+					let mut secret = server_secret.to_vec();
+					secret.extend(server_key.sign(&server_secret).to_vec());
+					format!("<pairingsecret>{}</pairingsecret>", to_hex(&secret))
+				} else if let Some(secret) = param("clientpairingsecret") {
+					// The client proves it owns its certificate, and that it got the challenge of the host.
+					let (client_secret, signature) = secret.split_at(16);
+					let client_certificate = CertificateDer::from_pem_slice(&client_certificate).unwrap();
+					let (client_signature, client_key) = certificate_details(&client_certificate).unwrap();
+					let signature = Signature::try_from(signature).unwrap();
+					let expected_hash = hash.digest(&[&server_challenge, &client_signature, client_secret]);
+					paired = VerifyingKey::<Sha256>::new(client_key).verify(client_secret, &signature).is_ok()
+						&& expected_hash == challenge_response_hash;
+					String::new()
+				} else {
+					paired = false;
+					String::new()
+				};
+
+				let body = format!("<root status_code=\"200\"><paired>{}</paired>{value}</root>", u8::from(paired));
+				let _ = write!(stream, "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{body}", body.len());
+			}
+		});
+
+		port
+	}
+
+	#[test]
+	fn pairs_with_hosts() {
+		let (client, server) = identities();
+
+		// Older hosts use SHA-1, newer ones SHA-256.
+		for app_version in ["7.1.431.-1", "3.20.3.63"] {
+			let port = mock_host(server.clone(), PIN, app_version);
+			let pairing = Pairing { host: "127.0.0.1", port, identity: client };
+
+			assert_eq!(pairing.handshake(PIN, app_version), Ok(server.certificate.clone()), "{app_version}");
+		}
+	}
+
+	#[test]
+	fn rejects_wrong_pin() {
+		let (client, server) = identities();
+		let port = mock_host(server.clone(), "1234", "7.1.431.-1");
+		let pairing = Pairing { host: "127.0.0.1", port, identity: client };
+
+		assert_eq!(pairing.handshake(PIN, "7.1.431.-1"), Err("The PIN was entered incorrectly.".to_string()));
+	}
+
+	#[test]
+	fn selects_hash_algorithm_by_version() {
+		assert!(matches!(HashAlgorithm::for_version("7.1.431.-1"), HashAlgorithm::Sha256));
+		assert!(matches!(HashAlgorithm::for_version("12.0"), HashAlgorithm::Sha256));
+		assert!(matches!(HashAlgorithm::for_version("6.0.0.0"), HashAlgorithm::Sha1));
+		assert!(matches!(HashAlgorithm::for_version(""), HashAlgorithm::Sha1));
+		assert!(matches!(HashAlgorithm::for_version("unknown"), HashAlgorithm::Sha1));
+
+		assert_eq!(HashAlgorithm::Sha1.digest(&[b"a", b"bc"]).len(), HashAlgorithm::Sha1.len());
+		assert_eq!(HashAlgorithm::Sha256.digest(&[b"a", b"bc"]), Sha256::digest(b"abc").to_vec());
+	}
+
+	#[test]
+	fn converts_hex() {
+		assert_eq!(to_hex(&[0x00, 0x0f, 0xab, 0xff]), "000fabff");
+		assert_eq!(from_hex("000fabff"), Some(vec![0x00, 0x0f, 0xab, 0xff]));
+		assert_eq!(from_hex("000FABFF"), Some(vec![0x00, 0x0f, 0xab, 0xff]));
+		assert_eq!(from_hex(""), Some(Vec::new()));
+		assert_eq!(from_hex("abc"), None);
+		assert_eq!(from_hex("zz"), None);
+		assert_eq!(from_hex("+1"), None);
+		assert_eq!(from_hex("é1"), None);
+	}
+
+	#[test]
+	fn encrypts_and_decrypts_blocks() {
+		let cipher = Aes128::new(GenericArray::from_slice(&[7; 16]));
+
+		let block = random_bytes::<16>();
+		let encrypted = encrypt(&cipher, &block);
+		assert_eq!(encrypted.len(), 16);
+		assert_ne!(encrypted, block);
+		assert_eq!(decrypt(&cipher, &encrypted), block);
+
+		// Data is padded with zeroes to whole blocks.
+		let data = b"challenge response of 36 bytes.....!";
+		let encrypted = encrypt(&cipher, data);
+		assert_eq!(encrypted.len(), 48);
+		let decrypted = decrypt(&cipher, &encrypted);
+		assert_eq!(&decrypted[..data.len()], data);
+		assert_eq!(decrypted[data.len()..], [0; 12]);
+	}
+}
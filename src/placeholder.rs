@@ -0,0 +1,160 @@
+use ab_glyph::{point, Font, FontRef, Glyph, PxScale, ScaleFont};
+use image::{Rgba, RgbaImage};
+
+use crate::artwork::ArtworkKind;
+
+/// Font used to draw the title of an app, bundled so placeholders look the same everywhere.
+const FONT: &[u8] = include_bytes!("../assets/fonts/DejaVuSans-Bold.ttf");
+
+/// Renders placeholder artwork for an app without boxart.
+///
+/// The background is a gradient with a color derived from the host, so apps of the same host look alike.
+pub fn render(kind: ArtworkKind, title: &str, host: &str) -> RgbaImage {
+	let (width, height) = kind.size();
+
+	match kind {
+		// The logo is drawn on top of the hero, so only the title is drawn.
+		ArtworkKind::Logo => {
+			let mut image = RgbaImage::new(width, height);
+			draw_title(&mut image, title, 0.9);
+			image
+		},
+		// The hero already has the logo drawn on top of it.
+		ArtworkKind::Hero => gradient(width, height, host),
+		ArtworkKind::Portrait | ArtworkKind::Landscape => {
+			let mut image = gradient(width, height, host);
+			draw_title(&mut image, title, 0.8);
+			image
+		},
+	}
+}
+
+/// Creates a diagonal gradient between two colors derived from the name of the host.
+fn gradient(width: u32, height: u32, host: &str) -> RgbaImage {
+	// FNV-1a, a simple hash that is stable across versions and platforms.
+	let hash = host.bytes().fold(0x811c9dc5u32, |h, b| (h ^ u32::from(b)).wrapping_mul(0x01000193));
+	let hue = (hash % 360) as f32;
+
+	let start = hsl_to_rgb(hue, 0.55, 0.38);
+	let end = hsl_to_rgb((hue + 40.0) % 360.0, 0.6, 0.16);
+
+	RgbaImage::from_fn(width, height, |x, y| {
+		let t = (x as f32 / width as f32 + y as f32 / height as f32) / 2.0;
+		let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+		Rgba([mix(start[0], end[0]), mix(start[1], end[1]), mix(start[2], end[2]), 255])
+	})
+}
+
+fn hsl_to_rgb(hue: f32, saturation: f32, lightness: f32) -> [u8; 3] {
+	let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+	let x = chroma * (1.0 - ((hue / 60.0) % 2.0 - 1.0).abs());
+	let m = lightness - chroma / 2.0;
+
+	let (r, g, b) = match hue as u32 {
+		0..=59 => (chroma, x, 0.0),
+		60..=119 => (x, chroma, 0.0),
+		120..=179 => (0.0, chroma, x),
+		180..=239 => (0.0, x, chroma),
+		240..=299 => (x, 0.0, chroma),
+		_ => (chroma, 0.0, x),
+	};
+
+	[r, g, b].map(|c| ((c + m) * 255.0).round() as u8)
+}
+
+/// Draws the title centered on the image, wrapping and shrinking it to fit within `fraction` of the image.
+fn draw_title(image: &mut RgbaImage, title: &str, fraction: f32) {
+	let font = FontRef::try_from_slice(FONT).expect("bundled font is valid");
+	let max_width = image.width() as f32 * fraction;
+	let max_height = image.height() as f32 * fraction;
+
+	// Start big and shrink until the wrapped title fits.
+	let mut size = image.height() as f32 / 4.0;
+	let (scale, lines) = loop {
+		let scale = PxScale::from(size);
+		let lines = wrap(&font.as_scaled(scale), title, max_width);
+		let fits = lines.len() as f32 * font.as_scaled(scale).height() <= max_height
+			&& lines.iter().all(|l| line_width(&font.as_scaled(scale), l) <= max_width);
+		if fits || size <= 8.0 {
+			break (scale, lines);
+		}
+		size *= 0.9;
+	};
+
+	let scaled = font.as_scaled(scale);
+	let line_height = scaled.height() + scaled.line_gap();
+	let total_height = line_height * lines.len() as f32 - scaled.line_gap();
+	let mut y = (image.height() as f32 - total_height) / 2.0 + scaled.ascent();
+
+	for line in &lines {
+		let mut x = (image.width() as f32 - line_width(&scaled, line)) / 2.0;
+		let mut previous = None;
+		for c in line.chars() {
+			let id = scaled.glyph_id(c);
+			if let Some(previous) = previous {
+				x += scaled.kern(previous, id);
+			}
+			previous = Some(id);
+
+			let glyph = Glyph { id, scale, position: point(x, y) };
+			x += scaled.h_advance(id);
+
+			// Draw a slightly offset shadow first, so the title is readable on any background.
+			for (offset, color) in [(scale.y / 30.0, [0, 0, 0]), (0.0, [255, 255, 255])] {
+				let mut glyph = glyph.clone();
+				glyph.position = point(glyph.position.x + offset, glyph.position.y + offset);
+				if let Some(outline) = font.outline_glyph(glyph) {
+					let bounds = outline.px_bounds();
+					outline.draw(|gx, gy, coverage| {
+						let px = bounds.min.x as i32 + gx as i32;
+						let py = bounds.min.y as i32 + gy as i32;
+						if px >= 0 && py >= 0 && (px as u32) < image.width() && (py as u32) < image.height() {
+							blend(image.get_pixel_mut(px as u32, py as u32), color, coverage);
+						}
+					});
+				}
+			}
+		}
+
+		y += line_height;
+	}
+}
+
+fn blend(pixel: &mut Rgba<u8>, color: [u8; 3], coverage: f32) {
+	let alpha = coverage.clamp(0.0, 1.0);
+	for i in 0..3 {
+		pixel[i] = (pixel[i] as f32 * (1.0 - alpha) + color[i] as f32 * alpha).round() as u8;
+	}
+	pixel[3] = pixel[3].max((alpha * 255.0).round() as u8);
+}
+
+fn line_width<F: Font>(font: &impl ScaleFont<F>, line: &str) -> f32 {
+	let mut width = 0.0;
+	let mut previous = None;
+	for c in line.chars() {
+		let id = font.glyph_id(c);
+		if let Some(previous) = previous {
+			width += font.kern(previous, id);
+		}
+		width += font.h_advance(id);
+		previous = Some(id);
+	}
+	width
+}
+
+/// Splits text in lines at word boundaries, so that each line fits within `max_width` when possible.
+fn wrap<F: Font>(font: &impl ScaleFont<F>, text: &str, max_width: f32) -> Vec<String> {
+	let mut lines: Vec<String> = Vec::new();
+
+	for word in text.split_whitespace() {
+		match lines.last_mut() {
+			Some(line) if line_width(font, &format!("{line} {word}")) <= max_width => {
+				line.push(' ');
+				line.push_str(word);
+			},
+			_ => lines.push(word.to_string()),
+		}
+	}
+
+	lines
+}
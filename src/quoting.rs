@@ -0,0 +1,167 @@
+use std::path::Path;
+
+/// Characters that can be used in an argument without quoting it.
+fn is_safe(c: char) -> bool {
+	c.is_ascii_alphanumeric() || "_-./:=@%+,".contains(c)
+}
+
+/// Quotes an argument for the shell that Steam runs shortcuts with.
+///
+/// Steam concatenates the executable and the launch options and passes them to `sh -c`, so arguments are put in
+/// double quotes (like Steam does itself), escaping the characters that are special within them.
+pub fn quote(arg: &str) -> String {
+	let mut quoted = String::with_capacity(arg.len() + 2);
+	quoted.push('"');
+	for c in arg.chars() {
+		if matches!(c, '"' | '\\' | '$' | '`') {
+			quoted.push('\\');
+		}
+		quoted.push(c);
+	}
+	quoted.push('"');
+	quoted
+}
+
+/// Quotes an argument only if it contains characters that the shell would interpret.
+fn quote_if_needed(arg: &str) -> String {
+	if !arg.is_empty() && arg.chars().all(is_safe) {
+		arg.to_string()
+	} else {
+		quote(arg)
+	}
+}
+
+/// Formats the path of an executable for the `exe` field of a shortcut.
+pub fn exe(path: &Path) -> String {
+	quote(&path.to_string_lossy())
+}
+
+/// Formats the launch options that stream an app of a host, followed by extra arguments for `moonlight stream`.
+pub fn stream_launch_options(host: &str, title: &str, args: &[String]) -> String {
+	let mut launch_options = format!("stream {} {}", quote(host), quote(title));
+	for arg in args {
+		launch_options.push(' ');
+		launch_options.push_str(&quote_if_needed(arg));
+	}
+	launch_options
+}
+
+/// Splits a command line into arguments the way `sh` does, without expanding anything.
+///
+/// This is the inverse of quoting the arguments, and is used to read back launch options.
+pub fn split(command_line: &str) -> Vec<String> {
+	let mut args = Vec::new();
+	let mut current: Option<String> = None;
+	let mut chars = command_line.chars();
+
+	while let Some(c) = chars.next() {
+		match c {
+			c if c.is_whitespace() => {
+				args.extend(current.take());
+				continue;
+			},
+			'\'' => current.get_or_insert_default().extend(chars.by_ref().take_while(|c| *c != '\'')),
+			'"' => {
+				let arg = current.get_or_insert_default();
+				while let Some(c) = chars.next() {
+					match c {
+						'"' => break,
+						'\\' => match chars.next() {
+							Some(c @ ('"' | '\\' | '$' | '`')) => arg.push(c),
+							Some('\n') => {},
+							Some(c) => arg.extend(['\\', c]),
+							None => arg.push('\\'),
+						},
+						c => arg.push(c),
+					}
+				}
+			},
+			'\\' => {
+				let arg = current.get_or_insert_default();
+				match chars.next() {
+					Some('\n') => {},
+					Some(c) => arg.push(c),
+					None => arg.push('\\'),
+				}
+			},
+			c => current.get_or_insert_default().push(c),
+		}
+	}
+
+	args.extend(current);
+	args
+}
+
+#[cfg(test)]
+mod tests {
+	use std::process::Command;
+
+	use super::*;
+
+	/// Titles that have broken launch options before.
+	const TITLES: &[&str] = &[
+		"Desktop",
+		"Steam Big Picture",
+		"Cyberpunk \"2077\"",
+		"Tom Clancy's The Division",
+		"$HOME and ${PATH}",
+		"Back\\slash\\",
+		"`rm -rf /`",
+		"$(echo injected)",
+		"Semi; colon && pipe | amp &",
+		"Glob * ? [a-z]",
+		"Tabs\tand\nnewlines",
+		"  leading and trailing  ",
+		"Ünïcödé ゲーム 🎮",
+		"!history !! expansion",
+		"#not a comment",
+		"~tilde",
+		"",
+	];
+
+	/// Runs the launch options through `sh`, the way Steam does, and returns the arguments it receives.
+	fn shell_args(command_line: &str) -> Vec<String> {
+		let output = Command::new("sh")
+			.args(["-c", &format!("printf '%s\\0' {command_line}")])
+			.output()
+			.unwrap();
+		assert!(output.status.success(), "sh failed for {command_line}");
+
+		let mut args: Vec<String> = String::from_utf8(output.stdout).unwrap().split('\0').map(String::from).collect();
+		args.pop();
+		args
+	}
+
+	#[test]
+	fn shell_receives_exact_titles() {
+		for title in TITLES {
+			let launch_options = stream_launch_options("Living Room", title, &["--fps".to_string(), "60".to_string()]);
+			assert_eq!(shell_args(&launch_options), ["stream", "Living Room", title, "--fps", "60"], "{launch_options}");
+		}
+	}
+
+	#[test]
+	fn shell_receives_exact_executable() {
+		for path in ["/usr/bin/moonlight", "/home/me/My Apps/moonlight", "/opt/$weird \"dir\"/moonlight"] {
+			assert_eq!(shell_args(&exe(Path::new(path))), [path]);
+		}
+	}
+
+	#[test]
+	fn splits_quoted_arguments() {
+		for title in TITLES {
+			let launch_options = stream_launch_options("Living Room", title, &["--resolution".to_string(), "1920x1080".to_string()]);
+			assert_eq!(split(&launch_options), ["stream", "Living Room", title, "--resolution", "1920x1080"]);
+		}
+
+		assert_eq!(split(r#"stream host "Old style""#), ["stream", "host", "Old style"]);
+		assert_eq!(split(r"a\ b 'c d' e"), ["a b", "c d", "e"]);
+	}
+
+	#[test]
+	fn quotes_only_when_needed() {
+		assert_eq!(quote_if_needed("1920x1080"), "1920x1080");
+		assert_eq!(quote_if_needed("a b"), "\"a b\"");
+		assert_eq!(quote_if_needed(""), "\"\"");
+	}
+}
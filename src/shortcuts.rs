@@ -0,0 +1,733 @@
+use std::{fs::File, io::Write, path::Path};
+
+use serde::{Deserialize, Serialize};
+use steam_shortcuts_util::{parse_shortcuts, shortcut::ShortcutOwned, shortcuts_to_bytes};
+
+use crate::{boxart, output::info, quoting};
+
+/// Tag that is added to every shortcut created by this tool.
+pub const MOONLIGHT_TAG: &str = "moonlight";
+
+/// Tag that identifies the shortcuts that were created for a specific host.
+pub fn host_tag(host: &str) -> String {
+	format!("{MOONLIGHT_TAG}:{host}")
+}
+
+/// Tag that identifies the shortcuts that were created for a stream profile.
+pub fn profile_tag(profile: &str) -> String {
+	format!("{PROFILE_TAG_PREFIX}{profile}")
+}
+
+/// Prefix of the tags for stream profiles.
+const PROFILE_TAG_PREFIX: &str = "moonlight-profile:";
+
+/// Returns the stream profile a shortcut was created for, if any.
+pub fn shortcut_profile(shortcut: &ShortcutOwned) -> Option<&str> {
+	shortcut.tags.iter().find_map(|t| t.strip_prefix(PROFILE_TAG_PREFIX))
+}
+
+/// Returns the host a shortcut was created for, or `None` if it isn't a (migrated) Moonlight shortcut.
+pub fn shortcut_host(shortcut: &ShortcutOwned) -> Option<&str> {
+	shortcut.tags.iter().find_map(|t| t.strip_prefix(MOONLIGHT_TAG)?.strip_prefix(':'))
+}
+
+/// Whether a shortcut was created by this tool.
+pub fn is_moonlight_shortcut(shortcut: &ShortcutOwned) -> bool {
+	shortcut.tags.iter().any(|t| t == MOONLIGHT_TAG) || shortcut_host(shortcut).is_some()
+}
+
+/// Whether a shortcut was created for the given host.
+pub fn is_host_shortcut(shortcut: &ShortcutOwned, host: &str) -> bool {
+	shortcut_host(shortcut) == Some(host)
+}
+
+/// Adds a host tag to shortcuts that only carry the bare "moonlight" tag.
+///
+/// Older versions didn't record the host, so it is parsed from the launch options instead,
+/// which have the form `stream <host> "<title>"`.
+/// Returns the number of shortcuts that were migrated.
+pub fn migrate_legacy_tags(shortcuts: &mut [ShortcutOwned]) -> usize {
+	let mut migrated = 0;
+
+	for shortcut in shortcuts {
+		if !shortcut.tags.iter().any(|t| t == MOONLIGHT_TAG) || shortcut_host(shortcut).is_some() {
+			continue;
+		}
+
+		let args = quoting::split(&shortcut.launch_options);
+		let host = match args.as_slice() {
+			[command, host, ..] if command == "stream" => Some(host.clone()),
+			_ => None,
+		};
+
+		match host {
+			Some(host) => {
+				shortcut.tags.push(host_tag(&host));
+				migrated += 1;
+			},
+			None => info!(
+				"Unable to determine host of shortcut '{}' from its launch options '{}', leaving it untouched.",
+				shortcut.app_name,
+				shortcut.launch_options,
+			),
+		}
+	}
+
+	migrated
+}
+
+/// What happens to the app ID of an existing shortcut when the executable or name it is derived from changes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum AppIdMode {
+	/// Keep the app ID, so artwork, collections and play time stay with the shortcut.
+	#[default]
+	Keep,
+
+	/// Change the app ID to the one Steam derives from the new executable and name, renaming the artwork and updating
+	/// the collections of the shortcut.
+	Remap,
+}
+
+/// Changes that were made to the shortcuts of a host by [`reconcile`].
+#[derive(Debug, Default)]
+pub struct Changes {
+	/// App IDs of shortcuts that were created.
+	pub added: Vec<u32>,
+
+	/// App IDs of existing shortcuts whose settings were changed.
+	pub updated: Vec<u32>,
+
+	/// App IDs of shortcuts that were removed, because their app no longer exists.
+	pub removed: Vec<u32>,
+
+	/// Number of existing shortcuts that were already up to date.
+	pub unchanged: usize,
+
+	/// App IDs of the shortcuts for the desired shortcuts, in the same order.
+	pub app_ids: Vec<u32>,
+
+	/// Fields that this tool set on the shortcuts for the desired shortcuts, in the same order.
+	pub managed: Vec<ManagedFields>,
+
+	/// Previous and new app IDs of the shortcuts whose app ID was remapped, see [`AppIdMode::Remap`].
+	pub remapped: Vec<(u32, u32)>,
+}
+
+impl Changes {
+	/// Whether no shortcut was added, updated, removed or remapped.
+	pub fn is_empty(&self) -> bool {
+		self.added.is_empty() && self.updated.is_empty() && self.removed.is_empty() && self.remapped.is_empty()
+	}
+}
+
+/// Fields of a shortcut that were set by this tool, as opposed to by the user.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ManagedFields {
+	/// App ID of the shortcut, to forget the fields once it is removed.
+	pub app_id: u32,
+
+	/// Whether the shortcut was hidden because of the policy for its app.
+	pub hidden: bool,
+
+	/// Tags that were added to the shortcut, from the host, stream profile and configuration.
+	pub tags: Vec<String>,
+}
+
+/// Shortcut that [`reconcile`] should create or update.
+#[derive(Debug)]
+pub struct DesiredShortcut {
+	pub shortcut: ShortcutOwned,
+
+	/// App ID of the shortcut that was created for the same app before, if it is known.
+	pub known_app_id: Option<u32>,
+
+	/// Fields that this tool set on the shortcut of the same app before.
+	pub managed: ManagedFields,
+}
+
+/// Brings the shortcuts of a host in line with the apps it currently provides.
+///
+/// Existing shortcuts are matched to apps by their known app ID, name or launch options (within the same stream
+/// profile), and only the fields this tool owns are updated: the name, the executable, the launch options, the icon
+/// while it is cached boxart and the managed tags. Everything else (play time, overlay settings, the hidden state, tags
+/// and icons set by the user) is left as is. Shortcuts for apps that no longer exist are removed when `prune` is set.
+///
+/// The hidden state and tags that this tool set before are passed along with the desired shortcuts, so shortcuts are
+/// only shown again if this tool hid them, and tags are only removed if this tool added them. What it sets now is
+/// returned in [`Changes::managed`].
+///
+/// Shortcuts that were created for the host at one of its `previous_addresses` are taken over by the current address.
+pub fn reconcile(
+	shortcuts: &mut Vec<ShortcutOwned>,
+	host: &str,
+	previous_addresses: &[String],
+	desired: Vec<DesiredShortcut>,
+	prune: bool,
+	app_ids: AppIdMode,
+) -> Changes {
+	let mut changes = Changes::default();
+	let mut matched = vec![false; shortcuts.len()];
+	let is_own = |s: &ShortcutOwned| is_host_shortcut(s, host) || previous_addresses.iter().any(|a| is_host_shortcut(s, a));
+
+	for DesiredShortcut { shortcut: new, known_app_id, managed: previous } in desired {
+		let candidate = |s: &ShortcutOwned, matched: bool| {
+			!matched && is_own(s) && shortcut_profile(s) == shortcut_profile(&new)
+		};
+		let existing = known_app_id
+			.and_then(|app_id| {
+				shortcuts.iter().zip(&matched).position(|(s, matched)| candidate(s, *matched) && s.app_id == app_id)
+			})
+			.or_else(|| {
+				shortcuts
+					.iter()
+					.zip(&matched)
+					.position(|(s, matched)| candidate(s, *matched) && s.app_name == new.app_name)
+			})
+			// Renamed shortcuts (after changing the title template) still launch the same app.
+			.or_else(|| {
+				shortcuts
+					.iter()
+					.zip(&matched)
+					.position(|(s, matched)| candidate(s, *matched) && s.launch_options == new.launch_options)
+			});
+
+		let Some(index) = existing else {
+			changes.managed.push(ManagedFields { app_id: new.app_id, hidden: new.is_hidden, tags: new.tags.clone() });
+			changes.added.push(new.app_id);
+			changes.app_ids.push(new.app_id);
+			shortcuts.push(new);
+			continue;
+		};
+
+		matched[index] = true;
+		// The derived app ID can only be used if no other shortcut has it, like one of another host with the same name.
+		let remap = app_ids == AppIdMode::Remap
+			&& shortcuts[index].app_id != new.app_id
+			&& !shortcuts.iter().any(|s| s.app_id == new.app_id);
+		let shortcut = &mut shortcuts[index];
+		let mut updated = false;
+
+		if remap {
+			changes.remapped.push((shortcut.app_id, new.app_id));
+			shortcut.app_id = new.app_id;
+			updated = true;
+		}
+		if shortcut.app_name != new.app_name {
+			shortcut.app_name = new.app_name;
+			updated = true;
+		}
+		if shortcut.exe != new.exe {
+			shortcut.exe = new.exe;
+			updated = true;
+		}
+		if shortcut.launch_options != new.launch_options {
+			shortcut.launch_options = new.launch_options;
+			updated = true;
+		}
+		// A shortcut that was already hidden by the user stays hidden after the policy no longer applies.
+		let hidden = new.is_hidden && (previous.hidden || !shortcut.is_hidden);
+		if shortcut.is_hidden != new.is_hidden && (new.is_hidden || previous.hidden) {
+			shortcut.is_hidden = new.is_hidden;
+			updated = true;
+		}
+		// Icons that were set by the user are kept, only missing ones and cached boxart are replaced.
+		let own_icon = shortcut.icon.is_empty() || boxart::is_cached(Path::new(&shortcut.icon));
+		if own_icon && !new.icon.is_empty() && shortcut.icon != new.icon {
+			shortcut.icon = new.icon;
+			updated = true;
+		}
+		// The tag of a previous address of the host is replaced by the one of the current address, and tags that are no
+		// longer generated (after changing the configuration) are removed.
+		let tag_count = shortcut.tags.len();
+		shortcut.tags.retain(|t| {
+			!previous_addresses.iter().any(|a| *t == host_tag(a)) && (new.tags.contains(t) || !previous.tags.contains(t))
+		});
+		updated |= shortcut.tags.len() != tag_count;
+		for tag in &new.tags {
+			if !shortcut.tags.contains(tag) {
+				shortcut.tags.push(tag.clone());
+				updated = true;
+			}
+		}
+
+		changes.managed.push(ManagedFields { app_id: shortcut.app_id, hidden, tags: new.tags });
+		changes.app_ids.push(shortcut.app_id);
+		if updated {
+			changes.updated.push(shortcut.app_id);
+		} else {
+			changes.unchanged += 1;
+		}
+	}
+
+	if prune {
+		// Shortcuts that were added above are beyond the end of `matched` and are always kept.
+		let mut index = 0;
+		shortcuts.retain(|s| {
+			let keep = matched.get(index).is_none_or(|m| *m) || !is_own(s);
+			if !keep {
+				changes.removed.push(s.app_id);
+			}
+			index += 1;
+			keep
+		});
+	}
+
+	changes
+}
+
+/// Reads the shortcuts from a shortcuts file, which has none when it doesn't exist yet.
+pub fn read(path: &Path) -> Result<Vec<ShortcutOwned>, String> {
+	if !path.exists() {
+		return Ok(Vec::new());
+	}
+
+	let contents = std::fs::read(path).map_err(|e| format!("Failed to read existing shortcuts file: {e}"))?;
+	Ok(parse_shortcuts(&contents)
+		.map_err(|e| format!("Failed to parse shortcuts: {e}"))?
+		.into_iter()
+		.map(|s| s.to_owned())
+		.collect())
+}
+
+/// Describes how the fields this tool owns differ between two versions of a shortcut.
+pub fn describe_update(old: &ShortcutOwned, new: &ShortcutOwned) -> Vec<String> {
+	let mut changes = Vec::new();
+
+	if old.app_name != new.app_name {
+		changes.push(format!("name: '{}' -> '{}'", old.app_name, new.app_name));
+	}
+	if old.app_id != new.app_id {
+		changes.push(format!("app ID: {} -> {}", old.app_id, new.app_id));
+	}
+	if old.exe != new.exe {
+		let kept = if old.app_id == new.app_id { format!(" (keeping app ID {})", new.app_id) } else { String::new() };
+		changes.push(format!("exe: '{}' -> '{}'{kept}", old.exe, new.exe));
+	}
+	if old.launch_options != new.launch_options {
+		changes.push(format!("launch options: '{}' -> '{}'", old.launch_options, new.launch_options));
+	}
+	if old.is_hidden != new.is_hidden {
+		let state = |hidden| if hidden { "hidden" } else { "visible" };
+		changes.push(format!("{} -> {}", state(old.is_hidden), state(new.is_hidden)));
+	}
+	if old.icon != new.icon {
+		changes.push(format!("icon: '{}' -> '{}'", old.icon, new.icon));
+	}
+	let tags: Vec<String> = new
+		.tags
+		.iter()
+		.filter(|t| !old.tags.contains(t))
+		.map(|t| format!("+{t}"))
+		.chain(old.tags.iter().filter(|t| !new.tags.contains(t)).map(|t| format!("-{t}")))
+		.collect();
+	if !tags.is_empty() {
+		changes.push(format!("tags: {}", tags.join(", ")));
+	}
+
+	changes
+}
+
+/// Writes shortcuts to a shortcuts file, see [`write_atomic`].
+pub fn write(path: &Path, shortcuts: &[ShortcutOwned]) -> Result<(), String> {
+	write_atomic(path, &encode_hidden_flags(shortcuts_to_bytes(&shortcuts.iter().map(ShortcutOwned::borrow).collect())))
+}
+
+/// Rewrites the `IsHidden` flags that are set from `1` to `0x01000001`.
+///
+/// The parser of `steam_shortcuts_util` mistakes a value starting with a `0x01` byte for a shorter encoding and reads
+/// a plain `1` back as `0`. This is the same encoding the library uses for the other flags, and Steam treats any
+/// value other than zero as set.
+fn encode_hidden_flags(mut bytes: Vec<u8>) -> Vec<u8> {
+	const HIDDEN: &[u8] = b"\x02IsHidden\x00\x01\x00\x00\x00";
+
+	let mut start = 0;
+	while let Some(offset) = bytes[start..].windows(HIDDEN.len()).position(|w| w == HIDDEN) {
+		let end = start + offset + HIDDEN.len();
+		bytes[end - 1] = 0x01;
+		start = end;
+	}
+	bytes
+}
+
+/// Replaces a shortcuts file with `contents`, without ever leaving a partially written file behind.
+///
+/// The contents are written to a temporary file in the same directory, which is synced to disk and read back
+/// to verify it parses as the same shortcuts, before it is renamed over the existing file.
+/// Missing directories (for accounts that don't have any shortcuts yet) are created.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> Result<(), String> {
+	let expected = parse_shortcuts(contents).map_err(|e| format!("Failed to parse shortcuts before writing: {e}"))?.len();
+	replace_verified(path, contents, expected)
+}
+
+/// Replaces a file with `contents` through a temporary file, which must read back as `expected` shortcuts.
+fn replace_verified(path: &Path, contents: &[u8], expected: usize) -> Result<(), String> {
+	let dir = path.parent().ok_or_else(|| format!("Failed to determine directory of '{}'.", path.display()))?;
+	std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create directory '{}': {e}", dir.display()))?;
+
+	let file_name = path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+	let temp_path = dir.join(format!(".{file_name}.{}.tmp", std::process::id()));
+	let result = write_verified(&temp_path, contents, expected)
+		.and_then(|_| std::fs::rename(&temp_path, path).map_err(|e| format!("Failed to replace '{}': {e}", path.display())));
+	if result.is_err() {
+		let _ = std::fs::remove_file(&temp_path);
+	}
+	result?;
+
+	// Sync the directory as well, otherwise the rename itself may be lost on a crash.
+	File::open(dir)
+		.and_then(|d| d.sync_all())
+		.map_err(|e| format!("Failed to sync directory '{}': {e}", dir.display()))
+}
+
+fn write_verified(path: &Path, contents: &[u8], expected: usize) -> Result<(), String> {
+	let mut file = File::create(path).map_err(|e| format!("Failed to create '{}': {e}", path.display()))?;
+	file.write_all(contents)
+		.and_then(|_| file.sync_all())
+		.map_err(|e| format!("Failed to write '{}': {e}", path.display()))?;
+
+	let written = std::fs::read(path).map_err(|e| format!("Failed to read back '{}': {e}", path.display()))?;
+	let parsed = parse_shortcuts(&written).map_err(|e| format!("Failed to parse written shortcuts: {e}"))?;
+	if written != contents || parsed.len() != expected {
+		return Err(format!("Shortcuts written to '{}' don't match, the existing file was left untouched.", path.display()));
+	}
+
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use std::path::PathBuf;
+
+	use steam_shortcuts_util::Shortcut;
+
+	use super::*;
+
+	const HOST: &str = "10.0.0.7";
+
+	/// Shortcut as this tool creates it for an app of a host.
+	fn shortcut(name: &str, host: &str, profile: Option<&str>) -> ShortcutOwned {
+		let launch_options = format!("stream {host} \"{name}\"");
+		let mut shortcut = Shortcut::new("0", name, "moonlight", "", "", "", &launch_options).to_owned();
+		shortcut.tags = [MOONLIGHT_TAG.to_string(), host_tag(host)]
+			.into_iter()
+			.chain(profile.map(profile_tag))
+			.collect();
+		shortcut
+	}
+
+	fn desired(shortcut: ShortcutOwned, known_app_id: Option<u32>) -> DesiredShortcut {
+		DesiredShortcut { shortcut, known_app_id, managed: ManagedFields::default() }
+	}
+
+	fn reconcile_host(shortcuts: &mut Vec<ShortcutOwned>, desired: Vec<DesiredShortcut>) -> Changes {
+		reconcile(shortcuts, HOST, &[], desired, true, AppIdMode::Keep)
+	}
+
+	#[test]
+	fn matches_by_known_app_id_then_name_then_launch_options() {
+		let alpha = shortcut("Alpha", HOST, None);
+		let beta = shortcut("Beta", HOST, None);
+		let gamma = shortcut("Gamma", HOST, None);
+		let app_ids = vec![alpha.app_id, beta.app_id, gamma.app_id];
+		let mut shortcuts = vec![alpha, beta, gamma.clone()];
+
+		// The app of the first shortcut was renamed to the name of another one, its known app ID still finds it.
+		let renamed = shortcut("Beta", HOST, None);
+		let mut retitled = shortcut("Gamma (1080p)", HOST, None);
+		retitled.launch_options = gamma.launch_options.clone();
+		let changes = reconcile_host(
+			&mut shortcuts,
+			vec![
+				desired(renamed, Some(app_ids[0])),
+				desired(shortcut("Beta", HOST, None), None),
+				desired(retitled, None),
+			],
+		);
+
+		assert_eq!(changes.app_ids, app_ids);
+		assert!(changes.added.is_empty() && changes.removed.is_empty());
+		assert_eq!(changes.updated, [app_ids[0], app_ids[2]]);
+		assert_eq!(changes.unchanged, 1);
+		let names: Vec<&str> = shortcuts.iter().map(|s| s.app_name.as_str()).collect();
+		assert_eq!(names, ["Beta", "Beta", "Gamma (1080p)"]);
+	}
+
+	#[test]
+	fn keeps_stream_profiles_apart() {
+		let hdr = shortcut("Desktop", HOST, Some("hdr"));
+		let mut shortcuts = vec![hdr.clone()];
+
+		let changes = reconcile_host(
+			&mut shortcuts,
+			vec![desired(shortcut("Desktop", HOST, None), None), desired(shortcut("Desktop", HOST, Some("hdr")), None)],
+		);
+
+		assert_eq!(changes.added.len(), 1);
+		assert_eq!(changes.unchanged, 1);
+		assert_eq!(shortcuts.len(), 2);
+		assert_eq!(shortcut_profile(&shortcuts[0]), Some("hdr"));
+		assert_eq!(shortcut_profile(&shortcuts[1]), None);
+	}
+
+	#[test]
+	fn prunes_only_shortcuts_of_the_host() {
+		let removed = shortcut("Removed", HOST, None);
+		let removed_app_id = removed.app_id;
+		let user = Shortcut::new("2", "User Game", "/usr/bin/game", "", "", "", "").to_owned();
+		let mut shortcuts = vec![removed, shortcut("Kept", HOST, None), shortcut("Other", "10.0.0.8", None), user];
+
+		let changes = reconcile_host(&mut shortcuts, vec![desired(shortcut("Kept", HOST, None), None)]);
+
+		assert_eq!(changes.removed, [removed_app_id]);
+		let names: Vec<&str> = shortcuts.iter().map(|s| s.app_name.as_str()).collect();
+		assert_eq!(names, ["Kept", "Other", "User Game"]);
+
+		let changes = reconcile(&mut shortcuts, HOST, &[], Vec::new(), false, AppIdMode::Keep);
+		assert!(changes.removed.is_empty());
+		assert_eq!(shortcuts.len(), 3);
+	}
+
+	#[test]
+	fn takes_over_shortcuts_of_previous_addresses() {
+		let old = shortcut("Desktop", "10.0.0.5", None);
+		let app_id = old.app_id;
+		let mut shortcuts = vec![old];
+
+		let changes = reconcile(
+			&mut shortcuts,
+			HOST,
+			&["10.0.0.5".to_string()],
+			vec![desired(shortcut("Desktop", HOST, None), None)],
+			true,
+			AppIdMode::Keep,
+		);
+
+		assert_eq!(changes.updated, [app_id]);
+		assert!(changes.removed.is_empty());
+		assert_eq!(shortcuts.len(), 1);
+		assert_eq!(shortcuts[0].launch_options, format!("stream {HOST} \"Desktop\""));
+		assert!(is_host_shortcut(&shortcuts[0], HOST));
+		assert!(!shortcuts[0].tags.contains(&host_tag("10.0.0.5")));
+	}
+
+	#[test]
+	fn keeps_or_remaps_app_ids() {
+		let existing = shortcut("Desktop", HOST, None);
+		let mut moved = shortcut("Desktop", HOST, None);
+		moved.exe = "/usr/bin/moonlight".to_string();
+		let mut remapped = moved.clone();
+		remapped.app_id = Shortcut::new("0", "Desktop", "/usr/bin/moonlight", "", "", "", "").to_owned().app_id;
+		assert_ne!(existing.app_id, remapped.app_id);
+
+		let mut shortcuts = vec![existing.clone()];
+		let changes = reconcile_host(&mut shortcuts, vec![desired(moved, None)]);
+		assert_eq!(shortcuts[0].app_id, existing.app_id);
+		assert_eq!(shortcuts[0].exe, "/usr/bin/moonlight");
+		assert!(changes.remapped.is_empty());
+
+		let mut shortcuts = vec![existing.clone()];
+		let new_app_id = remapped.app_id;
+		let wanted = vec![desired(remapped, None)];
+		let changes = reconcile(&mut shortcuts, HOST, &[], wanted, true, AppIdMode::Remap);
+		assert_eq!(shortcuts[0].app_id, new_app_id);
+		assert_eq!(changes.remapped, [(existing.app_id, new_app_id)]);
+		assert_eq!(changes.app_ids, [new_app_id]);
+	}
+
+	#[test]
+	fn preserves_fields_set_by_the_user() {
+		let boxart = "/home/deck/.cache/moonlight-steam-shortcuts/boxart/10.0.0.7/42.png";
+		let mut user = shortcut("Desktop", HOST, None);
+		user.is_hidden = true;
+		user.icon = "/home/deck/Pictures/desktop.png".to_string();
+		user.tags.push("favorite".to_string());
+		let mut tool = shortcut("Steam", HOST, None);
+		tool.is_hidden = true;
+		tool.icon = "/home/deck/.cache/Moonlight Game Streaming Project/Moonlight/boxart/1234/43.png".to_string();
+		tool.tags.push("old-tag".to_string());
+		let tags = [tool.tags.clone(), vec!["unused".to_string()]].concat();
+		let managed = ManagedFields { app_id: tool.app_id, hidden: true, tags };
+		let mut shortcuts = vec![user.clone(), tool.clone()];
+
+		let new = |name| {
+			let mut shortcut = shortcut(name, HOST, None);
+			shortcut.icon = boxart.to_string();
+			desired(shortcut, None)
+		};
+		let wanted = vec![new("Desktop"), DesiredShortcut { managed, ..new("Steam") }];
+		let changes = reconcile_host(&mut shortcuts, wanted);
+
+		// Hidden by the user, with their own icon and tag.
+		assert!(shortcuts[0].is_hidden);
+		assert_eq!(shortcuts[0].icon, user.icon);
+		assert_eq!(shortcuts[0].tags, user.tags);
+		// Hidden by the policy, with cached boxart and a tag from the configuration that no longer applies.
+		assert!(!shortcuts[1].is_hidden);
+		assert_eq!(shortcuts[1].icon, boxart);
+		assert_eq!(shortcuts[1].tags, [MOONLIGHT_TAG.to_string(), host_tag(HOST)]);
+		assert_eq!(changes.updated, [tool.app_id]);
+		assert_eq!(changes.unchanged, 1);
+		let expected = |app_id| ManagedFields { app_id, hidden: false, tags: shortcuts[1].tags.clone() };
+		assert_eq!(changes.managed, [expected(user.app_id), expected(tool.app_id)]);
+	}
+
+	#[test]
+	fn keeps_fields_of_hosts_sharing_app_ids_apart() {
+		const OTHER: &str = "10.0.0.8";
+		// Both hosts have a "Desktop" app, so their shortcuts get the same app ID.
+		let mut other = shortcut("Desktop", OTHER, None);
+		other.is_hidden = true;
+		other.tags.push("favorite".to_string());
+		let mut shortcuts = vec![shortcut("Desktop", HOST, None), other.clone()];
+		assert_eq!(shortcuts[0].app_id, other.app_id);
+
+		// The first host hides its shortcut and adds a tag.
+		let mut hidden = shortcut("Desktop", HOST, None);
+		hidden.is_hidden = true;
+		hidden.tags.push("favorite".to_string());
+		let changes = reconcile_host(&mut shortcuts, vec![desired(hidden, None)]);
+		assert!(shortcuts[0].is_hidden);
+		assert!(changes.managed[0].hidden);
+
+		// The record of the first host doesn't apply to the shortcut the user hid and tagged on the other host.
+		let wanted = vec![desired(shortcut("Desktop", OTHER, None), None)];
+		let changes = reconcile(&mut shortcuts, OTHER, &[], wanted, true, AppIdMode::Keep);
+		assert_eq!(changes.unchanged, 1);
+		assert!(shortcuts[1].is_hidden);
+		assert_eq!(shortcuts[1].tags, other.tags);
+		assert!(!changes.managed[0].hidden);
+		assert!(shortcuts[0].is_hidden);
+	}
+
+	#[test]
+	fn removes_stale_tags_only_of_the_own_host() {
+		const OTHER: &str = "10.0.0.8";
+		let mut own = shortcut("Desktop", HOST, None);
+		own.tags.push("hdr".to_string());
+		let mut other = shortcut("Desktop", OTHER, None);
+		other.tags.push("hdr".to_string());
+		let mut shortcuts = vec![own.clone(), other.clone()];
+
+		// The configuration of the first host no longer adds the "hdr" tag, the user added it on the other host.
+		let managed = ManagedFields { app_id: own.app_id, hidden: false, tags: own.tags.clone() };
+		let wanted = vec![DesiredShortcut { managed, ..desired(shortcut("Desktop", HOST, None), None) }];
+		let changes = reconcile_host(&mut shortcuts, wanted);
+		assert_eq!(changes.updated, [own.app_id]);
+		assert_eq!(shortcuts[0].tags, [MOONLIGHT_TAG.to_string(), host_tag(HOST)]);
+
+		let wanted = vec![desired(shortcut("Desktop", OTHER, None), None)];
+		let changes = reconcile(&mut shortcuts, OTHER, &[], wanted, true, AppIdMode::Keep);
+		assert_eq!(changes.unchanged, 1);
+		assert_eq!(shortcuts[1].tags, other.tags);
+		assert_eq!(changes.managed[0].tags, [MOONLIGHT_TAG.to_string(), host_tag(OTHER)]);
+	}
+
+	#[test]
+	fn hidden_flag_survives_round_trip() {
+		let mut hidden = Shortcut::new("0", "Hidden", "moonlight", "", "", "", "stream host Hidden").to_owned();
+		hidden.is_hidden = true;
+		let visible = Shortcut::new("1", "Visible", "moonlight", "", "", "", "stream host Visible").to_owned();
+
+		let shortcuts = [hidden, visible];
+		let bytes = encode_hidden_flags(shortcuts_to_bytes(&shortcuts.iter().map(ShortcutOwned::borrow).collect()));
+		let parsed = parse_shortcuts(&bytes).unwrap();
+
+		assert!(parsed[0].is_hidden);
+		assert!(!parsed[1].is_hidden);
+		assert_eq!(parsed[1].app_name, "Visible");
+	}
+
+	#[test]
+	fn migrates_tags_of_legacy_shortcuts() {
+		let legacy = |name: &str, launch_options: &str| {
+			let mut shortcut = Shortcut::new("0", name, "moonlight", "", "", "", launch_options).to_owned();
+			shortcut.tags = vec![MOONLIGHT_TAG.to_string(), "Favorites".to_string()];
+			shortcut
+		};
+		let mut shortcuts = vec![
+			legacy("Desktop", "stream 10.0.0.7 Desktop"),
+			legacy("Say \"Hi\"", r#"stream "gaming pc" "Say \"Hi\"" --fps 60"#),
+			legacy("It's", r"stream 'host'\''s pc' 'It'\''s'"),
+			legacy("Listed", "list 10.0.0.7"),
+			legacy("Empty", ""),
+			legacy("Unclosed", "stream"),
+			shortcut("Steam", "10.0.0.8", None),
+			Shortcut::new("0", "Other", "moonlight", "", "", "", "stream 10.0.0.9 Other").to_owned(),
+		];
+		let untouched: Vec<_> = shortcuts[3..].iter().map(|s| (s.launch_options.clone(), s.tags.clone())).collect();
+
+		assert_eq!(migrate_legacy_tags(&mut shortcuts), 3);
+		assert_eq!(shortcuts[0].tags, ["moonlight", "Favorites", "moonlight:10.0.0.7"]);
+		assert_eq!(shortcuts[1].tags, ["moonlight", "Favorites", "moonlight:gaming pc"]);
+		assert_eq!(shortcuts[2].tags, ["moonlight", "Favorites", "moonlight:host's pc"]);
+		let after: Vec<_> = shortcuts[3..].iter().map(|s| (s.launch_options.clone(), s.tags.clone())).collect();
+		assert_eq!(after, untouched);
+
+		// Shortcuts that were migrated already aren't tagged twice.
+		assert_eq!(migrate_legacy_tags(&mut shortcuts), 0);
+		assert_eq!(shortcuts[0].tags, ["moonlight", "Favorites", "moonlight:10.0.0.7"]);
+	}
+
+	/// Empty directory for a test, which is removed again when the test passes.
+	fn temp_dir(name: &str) -> PathBuf {
+		let dir = std::env::temp_dir().join(format!("moonlight-steam-shortcuts-{}-{name}", std::process::id()));
+		let _ = std::fs::remove_dir_all(&dir);
+		std::fs::create_dir_all(&dir).unwrap();
+		dir
+	}
+
+	fn to_bytes(shortcuts: &[ShortcutOwned]) -> Vec<u8> {
+		shortcuts_to_bytes(&shortcuts.iter().map(ShortcutOwned::borrow).collect())
+	}
+
+	fn dir_entries(dir: &Path) -> Vec<String> {
+		let mut entries: Vec<String> =
+			std::fs::read_dir(dir).unwrap().map(|e| e.unwrap().file_name().to_string_lossy().to_string()).collect();
+		entries.sort();
+		entries
+	}
+
+	#[test]
+	fn writes_shortcuts_atomically() {
+		let dir = temp_dir("write-atomic");
+		let path = dir.join("userdata/123/config/shortcuts.vdf");
+
+		// The config directory doesn't exist yet for accounts without shortcuts.
+		let first = to_bytes(&[shortcut("Desktop", HOST, None)]);
+		write_atomic(&path, &first).unwrap();
+		assert_eq!(std::fs::read(&path).unwrap(), first);
+
+		let second = to_bytes(&[shortcut("Desktop", HOST, None), shortcut("Steam", HOST, None)]);
+		write_atomic(&path, &second).unwrap();
+		assert_eq!(std::fs::read(&path).unwrap(), second);
+		assert_eq!(dir_entries(path.parent().unwrap()), ["shortcuts.vdf"]);
+
+		assert!(write_atomic(&path, b"not shortcuts").is_err());
+		assert_eq!(std::fs::read(&path).unwrap(), second);
+
+		std::fs::remove_dir_all(&dir).unwrap();
+	}
+
+	#[test]
+	fn keeps_the_existing_file_when_verification_fails() {
+		let dir = temp_dir("write-verified");
+		let path = dir.join("shortcuts.vdf");
+		std::fs::write(&path, b"existing").unwrap();
+
+		let contents = to_bytes(&[shortcut("Desktop", HOST, None)]);
+		let error = replace_verified(&path, &contents, 2).unwrap_err();
+		assert!(error.contains("don't match"), "{error}");
+		assert_eq!(std::fs::read(&path).unwrap(), b"existing");
+		assert_eq!(dir_entries(&dir), ["shortcuts.vdf"]);
+
+		// The temporary file is removed as well when it can't be renamed over the target.
+		let target = dir.join("directory");
+		std::fs::create_dir_all(target.join("not-empty")).unwrap();
+		assert!(write_atomic(&target, &contents).is_err());
+		assert_eq!(dir_entries(&dir), ["directory", "shortcuts.vdf"]);
+
+		std::fs::remove_dir_all(&dir).unwrap();
+	}
+}
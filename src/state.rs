@@ -0,0 +1,105 @@
+use std::{collections::BTreeMap, path::{Path, PathBuf}};
+
+use serde::{Deserialize, Serialize};
+use steam_shortcuts_util::shortcut::ShortcutOwned;
+
+use crate::{artwork::GeneratedArtwork, config::APP_NAME, shortcuts::ManagedFields};
+
+/// What is remembered about the syncs for a Steam user.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SyncState {
+	/// State of each host that was synced, by address.
+	pub hosts: BTreeMap<String, HostState>,
+
+	/// App IDs of the shortcuts that were created for apps, by UUID of the host and [`app_key`].
+	///
+	/// This finds the shortcut of an app again after the app was renamed or the host got another address.
+	pub shortcuts: BTreeMap<String, BTreeMap<String, u32>>,
+
+	/// Fields that this tool set on the shortcuts of apps, by UUID (or address, if unknown) of the host and
+	/// [`app_key`].
+	///
+	/// App IDs can't be used for this, since the shortcuts of apps with the same name on different hosts share them.
+	pub managed: BTreeMap<String, BTreeMap<String, ManagedFields>>,
+
+	/// Artwork that this tool generated, by app ID of the shortcut, which is what Steam names the artwork files after.
+	pub artwork: BTreeMap<u32, GeneratedArtwork>,
+}
+
+impl SyncState {
+	/// Returns the other addresses that the host with this UUID was synced from before.
+	pub fn previous_addresses(&self, uuid: &str, address: &str) -> Vec<String> {
+		self.hosts
+			.iter()
+			.filter(|(a, h)| *a != address && h.uuid.as_deref() == Some(uuid))
+			.map(|(a, _)| a.clone())
+			.collect()
+	}
+
+	/// Forgets the shortcuts that no longer exist, so their app IDs aren't matched to new shortcuts.
+	pub fn forget_removed(&mut self, shortcuts: &[ShortcutOwned]) {
+		let exists = |app_id: u32| shortcuts.iter().any(|s| s.app_id == app_id);
+
+		for known in self.shortcuts.values_mut() {
+			known.retain(|_, app_id| exists(*app_id));
+		}
+		self.shortcuts.retain(|_, known| !known.is_empty());
+		for fields in self.managed.values_mut() {
+			fields.retain(|_, f| exists(f.app_id));
+		}
+		self.managed.retain(|_, fields| !fields.is_empty());
+		self.artwork.retain(|app_id, _| exists(*app_id));
+	}
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HostState {
+	/// Time of the last successful sync, in seconds since the Unix epoch.
+	pub last_sync: Option<u64>,
+
+	/// UUID of the host at this address, if it was known during the last sync.
+	pub uuid: Option<String>,
+}
+
+/// Key of the shortcut of an app in [`SyncState::shortcuts`]: the ID of the app on the host, followed by the stream
+/// profile of the shortcut, if any.
+pub fn app_key(app_id: u32, profile: Option<&str>) -> String {
+	match profile {
+		Some(profile) => format!("{app_id}:{profile}"),
+		None => app_id.to_string(),
+	}
+}
+
+/// Location of the sync state of a Steam user, named after the user directory (the account ID).
+fn state_path(userdata_dir: &Path) -> Result<PathBuf, String> {
+	let user = userdata_dir
+		.file_name()
+		.ok_or_else(|| format!("Failed to determine Steam user of '{}'.", userdata_dir.display()))?;
+
+	let dir = xdg::BaseDirectories::with_prefix(APP_NAME)
+		.map_err(|e| format!("Failed to determine state directory: {e}"))?
+		.create_state_directory("sync")
+		.map_err(|e| format!("Failed to create state directory: {e}"))?;
+
+	Ok(dir.join(user).with_extension("toml"))
+}
+
+/// Loads the sync state of a Steam user, which is empty if it was never synced.
+pub fn load(userdata_dir: &Path) -> Result<SyncState, String> {
+	let path = state_path(userdata_dir)?;
+	if !path.exists() {
+		return Ok(SyncState::default());
+	}
+
+	let contents = std::fs::read_to_string(&path)
+		.map_err(|e| format!("Failed to read sync state '{}': {e}", path.display()))?;
+	toml::from_str(&contents).map_err(|e| format!("Failed to parse sync state '{}': {e}", path.display()))
+}
+
+pub fn save(userdata_dir: &Path, state: &SyncState) -> Result<(), String> {
+	let path = state_path(userdata_dir)?;
+	let contents = toml::to_string(state).map_err(|e| format!("Failed to serialize sync state: {e}"))?;
+	std::fs::write(&path, contents).map_err(|e| format!("Failed to write sync state '{}': {e}", path.display()))
+}
@@ -0,0 +1,153 @@
+use std::{path::PathBuf, process::{Command, Stdio}, thread, time::{Duration, Instant}};
+
+use serde::Deserialize;
+
+use crate::output::info;
+
+/// Flatpak application ID of Steam.
+const FLATPAK_ID: &str = "com.valvesoftware.Steam";
+
+/// How long to wait for Steam to exit after asking it to shut down.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How long to wait for the user to close Steam.
+const WAIT_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// What to do when Steam is running while the shortcuts file is written.
+///
+/// Steam writes its shortcuts back to disk when it exits, reverting any changes made in the meantime.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum RunningPolicy {
+	/// Don't write the shortcuts file.
+	#[default]
+	Refuse,
+
+	/// Write the shortcuts file anyway, after printing a warning.
+	Warn,
+
+	/// Wait (up to 30 minutes) for Steam to exit before writing.
+	Wait,
+
+	/// Shut Steam down, write the shortcuts file and start Steam again.
+	Restart,
+}
+
+/// A Steam client process.
+#[derive(Debug, Clone, Copy)]
+pub struct SteamProcess {
+	pub pid: u32,
+
+	/// Whether this is the Flatpak version of Steam.
+	pub flatpak: bool,
+}
+
+impl SteamProcess {
+	/// Command that runs Steam, for the installation this process belongs to.
+	fn command(&self) -> Command {
+		if self.flatpak {
+			let mut command = Command::new("flatpak");
+			command.args(["run", FLATPAK_ID]);
+			command
+		} else {
+			Command::new("steam")
+		}
+	}
+}
+
+/// Finds a running Steam client, using the PID file native Steam writes and by scanning `/proc`.
+///
+/// Flatpak Steam writes its PID file from inside its sandbox, where the PID means nothing to the host, so it is only
+/// found by the scan and told apart by its cgroup.
+pub fn find_running() -> Option<SteamProcess> {
+	let from_pid_file = pid_file()
+		.and_then(|path| std::fs::read_to_string(path).ok()?.trim().parse().ok())
+		.filter(|pid| is_steam(*pid));
+
+	// The PID file is left behind when Steam crashes, and is missing for other installations.
+	let pid = from_pid_file.or_else(|| {
+		std::fs::read_dir("/proc")
+			.ok()?
+			.filter_map(Result::ok)
+			.filter_map(|entry| entry.file_name().to_str()?.parse().ok())
+			.find(|pid| is_steam(*pid))
+	})?;
+
+	Some(SteamProcess { pid, flatpak: is_flatpak(pid) })
+}
+
+/// Location of the PID file of native Steam.
+fn pid_file() -> Option<PathBuf> {
+	std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".steam/steam.pid"))
+}
+
+fn is_steam(pid: u32) -> bool {
+	std::fs::read_to_string(format!("/proc/{pid}/comm")).is_ok_and(|name| name.trim() == "steam")
+}
+
+fn is_flatpak(pid: u32) -> bool {
+	std::fs::read_to_string(format!("/proc/{pid}/cgroup")).is_ok_and(|cgroup| cgroup.contains(FLATPAK_ID))
+}
+
+/// Runs `write` while making sure Steam won't overwrite the result, according to `policy`.
+pub fn guard<T>(policy: RunningPolicy, write: impl FnOnce() -> Result<T, String>) -> Result<T, String> {
+	let Some(steam) = find_running() else {
+		return write();
+	};
+
+	match policy {
+		RunningPolicy::Refuse => Err(format!(
+			"Steam is running (PID {}) and would revert the changes when it exits. Close Steam first, \
+			or use `--steam-running` to warn, wait or restart Steam instead.",
+			steam.pid,
+		)),
+		RunningPolicy::Warn => {
+			info!("Warning: Steam is running (PID {}), changes may be reverted when it exits.", steam.pid);
+			write()
+		},
+		RunningPolicy::Wait => {
+			info!("Waiting for Steam (PID {}) to exit ...", steam.pid);
+			wait_for_exit(WAIT_TIMEOUT)?;
+			write()
+		},
+		RunningPolicy::Restart => {
+			shutdown(steam)?;
+			let result = write();
+
+			// Start Steam again even if writing failed, it was running before.
+			info!("Starting Steam ...");
+			let started = steam.command().stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null()).spawn();
+
+			match (result, started) {
+				(result, Ok(_)) => result,
+				(Ok(_), Err(e)) => Err(format!("Wrote the shortcuts file, but failed to start Steam again: {e}")),
+				(Err(write_error), Err(e)) => Err(format!("{write_error}; failed to start Steam again as well: {e}")),
+			}
+		},
+	}
+}
+
+fn shutdown(steam: SteamProcess) -> Result<(), String> {
+	info!("Shutting down Steam (PID {}) ...", steam.pid);
+	steam.command()
+		.arg("-shutdown")
+		.stdin(Stdio::null())
+		.stdout(Stdio::null())
+		.stderr(Stdio::null())
+		.status()
+		.map_err(|e| format!("Failed to shut down Steam: {e}"))?;
+
+	wait_for_exit(SHUTDOWN_TIMEOUT)
+}
+
+fn wait_for_exit(timeout: Duration) -> Result<(), String> {
+	let start = Instant::now();
+	while find_running().is_some() {
+		if start.elapsed() > timeout {
+			return Err(format!("Steam didn't exit within {} seconds.", timeout.as_secs()));
+		}
+		thread::sleep(Duration::from_secs(1));
+	}
+
+	Ok(())
+}
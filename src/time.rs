@@ -0,0 +1,57 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Point in time in UTC, split into its calendar fields.
+#[derive(Debug, Clone, Copy)]
+pub struct UtcTime {
+	pub year: i64,
+	pub month: u64,
+	pub day: u64,
+	pub hour: u64,
+	pub minute: u64,
+	pub second: u64,
+}
+
+impl UtcTime {
+	pub fn now() -> Self {
+		Self::from_unix(unix_now())
+	}
+
+	/// Converts seconds since the Unix epoch to a date in the Gregorian calendar, see
+	/// https://howardhinnant.github.io/date_algorithms.html#civil_from_days
+	pub fn from_unix(seconds: u64) -> Self {
+		let (days, seconds) = (seconds / 86400, seconds % 86400);
+
+		let days = days as i64 + 719468;
+		let era = days.div_euclid(146097);
+		let day_of_era = days.rem_euclid(146097);
+		let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+		let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+		let month_index = (5 * day_of_year + 2) / 153;
+		let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+		let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+
+		Self {
+			year: year_of_era + era * 400 + i64::from(month <= 2),
+			month: month as u64,
+			day: day as u64,
+			hour: seconds / 3600,
+			minute: seconds % 3600 / 60,
+			second: seconds % 60,
+		}
+	}
+}
+
+impl std::fmt::Display for UtcTime {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(
+			f,
+			"{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+			self.year, self.month, self.day, self.hour, self.minute, self.second,
+		)
+	}
+}
+
+/// Returns the number of seconds since the Unix epoch.
+pub fn unix_now() -> u64 {
+	SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default()
+}